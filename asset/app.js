@@ -5,10 +5,11 @@ createApp({
             recentTitles: [],
             detailObject: null ,
             ongoing: new Map(),
+            mode: 'single',
             error: null,
             newStart: '',
             report: null,
-            queryParam: {'dayStart': "", "dayEnd": "", 'viewType': "daily_detail"},
+            queryParam: {'dayStart': "", "dayEnd": "", 'viewType': "daily_detail", 'filter': "", 'splitOverlaps': false},
         }
     },
 
@@ -30,14 +31,23 @@ createApp({
             let unfinished = await (await fetch(url)).json();
             let m = new Map();
             for (const element of unfinished) {
-                m.set(element.title, {'item': element, 'notes': ''});
+                m.set(element.id.title, {'item': element, 'notes': ''});
             };
             this.ongoing = m;
         },
 
+        async getMode() {
+            const url = '/api/mode/';
+            this.mode = await (await fetch(url)).json();
+        },
+        canStart() {
+            return this.ongoing.size == 0 || this.mode == 'parallel';
+        },
+
         getData() {
             this.getRecent();
             this.getUnfinished();
+            this.getMode();
             this.error = null;
         },
         async start(title) {
@@ -57,8 +67,42 @@ createApp({
                    }).catch((err) => this.error = err))
         },
         async finish(title) {
-            let url = `/api/finish/`;
-            await (fetch(url, {method: 'POST', body: this.ongoing.get(title).notes})
+            let obj = this.ongoing.get(title);
+            let url = `/api/finish/${encodeURIComponent(title)}/${encodeURIComponent(obj.item.id.start)}`;
+            await (fetch(url, {method: 'POST', body: obj.notes})
+                   .then((rep) => {
+                       if (rep.ok) {
+                           this.getData();
+                       } else {
+                           this.error = `${rep.status}`;
+                       }
+                   }).catch((err) => this.error = err))
+        },
+        async switchTo(title) {
+            if (title == null || title.length == 0) {
+                this.error = "Empty title";
+                return;
+            }
+
+            let notes = Array.from(this.ongoing.values()).map((obj) => obj.notes).join('\n');
+            let url = `/api/switch/${encodeURI(title)}`;
+            await (fetch(url, {method: 'POST', body: notes})
+                   .then((rep) => {
+                       if (rep.ok) {
+                           this.getData();
+                       } else {
+                           this.error = `${rep.status}`;
+                       }
+                   }).catch((err) => this.error = err))
+        },
+        isPaused(item) {
+            return item.pauses.length > 0 && item.pauses[item.pauses.length - 1].end == null;
+        },
+        async togglePause(title) {
+            let item = this.ongoing.get(title).item;
+            let action = this.isPaused(item) ? 'resume' : 'pause';
+            let url = `/api/${action}/${encodeURIComponent(title)}/${encodeURIComponent(item.id.start)}`;
+            await (fetch(url, {method: 'POST'})
                    .then((rep) => {
                        if (rep.ok) {
                            this.getData();
@@ -67,12 +111,18 @@ createApp({
                        }
                    }).catch((err) => this.error = err))
         },
-        async getReportByDate(dayStart, dayEnd, viewType) {
+        async getReportByDate(dayStart, dayEnd, viewType, filter) {
             this.report = null;
             if (dayStart == "" || dayEnd == "") {
                 this.error = "Query start and end must be specified.";
             } else {
                 let url = `/api/report-by-date/${dayStart}/${dayEnd}?view_type=${viewType}`;
+                if (filter) {
+                    url += `&filter=${encodeURIComponent(filter)}`;
+                }
+                if (this.queryParam.splitOverlaps) {
+                    url += '&split_overlaps=true';
+                }
                 this.report = await (await fetch(url)).text();
             }
         },
@@ -91,7 +141,7 @@ createApp({
 
             this.queryParam.dayStart = this.getDateString(new Date(start_t));
             this.queryParam.dayEnd = this.getDateString(new Date(end_t));
-            this.getReportByDate(this.queryParam.dayStart, this.queryParam.dayEnd, this.queryParam.viewType);
+            this.getReportByDate(this.queryParam.dayStart, this.queryParam.dayEnd, this.queryParam.viewType, this.queryParam.filter);
         },
         getDateString(d) {
             return `${d.getFullYear()}-${(d.getMonth() + 1).toString().padStart(2, '0')}-${d.getDate().toString().padStart(2, '0')}`;
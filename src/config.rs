@@ -0,0 +1,282 @@
+//! Persistent configuration at `$XDG_CONFIG_HOME/clocking/config.toml`.
+//!
+//! Every key is optional. Command line arguments take precedence over the environment variable
+//! `CLOCKING_FILE`, which takes precedence over the file, see [`Config::path`] for where it is.
+use crate::errors::Error;
+use crate::types::{Workday, WEEKDAYS};
+use crate::Result;
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+
+/// Environment variable of the config file path, which overrides the default one.
+pub const CONFIG_FILE_VAR: &str = "CLOCKING_CONFIG";
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Storage file, used if neither `--file` nor `CLOCKING_FILE` is given. A leading `~/` is
+    /// expanded to the home directory, and a relative path is relative to the directory of the
+    /// config file, by [`Config::load`].
+    pub file: Option<String>,
+    pub report: ReportConfig,
+    pub workday: WorkdayConfig,
+    pub server: ServerConfig,
+    pub limits: Limits,
+}
+
+/// Defaults of `clocking report`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportConfig {
+    /// View shown if no view is chosen by arguments.
+    pub view: ReportView,
+    /// First day of week of weekly views and goals.
+    pub week_start: Weekday,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        ReportConfig {
+            view: ReportView::DailyDetail,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+/// Views of `clocking report`.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportView {
+    DailyDetail,
+    Daily,
+    Detail,
+    Dist,
+    Weekly,
+    WeeklyDetail,
+    Monthly,
+    MonthlyDetail,
+    Tag,
+    Tree,
+    Billing,
+}
+
+/// Working hours overriding the ones saved in the store, like `hours = "09:00-17:00"` for every
+/// day and `sat = "off"` for Saturday.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkdayConfig {
+    pub hours: Option<String>,
+    pub mon: Option<String>,
+    pub tue: Option<String>,
+    pub wed: Option<String>,
+    pub thu: Option<String>,
+    pub fri: Option<String>,
+    pub sat: Option<String>,
+    pub sun: Option<String>,
+}
+
+impl WorkdayConfig {
+    /// Configured hours of `weekday`, if any.
+    fn hours_of(&self, weekday: Weekday) -> Option<&String> {
+        let hours = match weekday {
+            Weekday::Mon => &self.mon,
+            Weekday::Tue => &self.tue,
+            Weekday::Wed => &self.wed,
+            Weekday::Thu => &self.thu,
+            Weekday::Fri => &self.fri,
+            Weekday::Sat => &self.sat,
+            Weekday::Sun => &self.sun,
+        };
+        hours.as_ref().or(self.hours.as_ref())
+    }
+
+    /// Weekdays whose hours are overridden by the config.
+    pub fn overridden(&self) -> Vec<Weekday> {
+        WEEKDAYS
+            .into_iter()
+            .filter(|weekday| self.hours_of(*weekday).is_some())
+            .collect()
+    }
+
+    /// Override hours of `workday` by the configured ones, see [`Workday::parse_hours`].
+    pub fn apply(&self, mut workday: Workday) -> Result<Workday> {
+        for weekday in WEEKDAYS {
+            if let Some(hours) = self.hours_of(weekday) {
+                workday.set_hours(weekday, Workday::parse_hours(hours)?)?;
+            }
+        }
+        Ok(workday)
+    }
+}
+
+/// Where `clocking server` listens, overridden by `--addr` and `--port`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub addr: IpAddr,
+    pub port: u16,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8080,
+        }
+    }
+}
+
+/// Numbers of items listed.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Recent titles to choose from when a command is not given a title.
+    pub recent_titles: usize,
+    /// Titles listed by `/api/recent` of the server.
+    pub api_recent: usize,
+    /// Unfinished entries listed by `/api/unfinished` of the server.
+    pub api_unfinished: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            recent_titles: 5,
+            api_recent: 5,
+            api_unfinished: 10,
+        }
+    }
+}
+
+impl Config {
+    /// Path of the config file: [`CONFIG_FILE_VAR`] if set, or `clocking/config.toml` in
+    /// `$XDG_CONFIG_HOME`, which defaults to `~/.config`.
+    pub fn path() -> Option<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        if let Some(path) = var(CONFIG_FILE_VAR) {
+            return Some(PathBuf::from(path));
+        }
+        let config_home = var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("clocking").join("config.toml"))
+    }
+
+    /// Read the config at `path`, the default one if it does not exist.
+    pub fn load(path: &Path) -> Result<Config> {
+        let mut config: Config = match std::fs::read_to_string(path) {
+            Ok(text) => text.parse()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(Error::UnderlyingError(format!("{}: {e}", path.display()))),
+        };
+        let home = std::env::var_os("HOME").filter(|v| !v.is_empty());
+        config.file = config
+            .file
+            .map(|file| resolve_file(file, home.as_deref().map(Path::new), path.parent()));
+        Ok(config)
+    }
+
+    /// The config as toml, keys not set are left out.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("config is always serializable")
+    }
+}
+
+/// Expand a leading `~/` of `file` to `home`, and resolve a relative `file` against `config_dir`.
+///
+/// `file` is unchanged by the missing `home` or `config_dir`.
+fn resolve_file(file: String, home: Option<&Path>, config_dir: Option<&Path>) -> String {
+    match (file.strip_prefix("~/"), home, config_dir) {
+        (Some(rest), Some(home), _) => home.join(rest).to_string_lossy().into_owned(),
+        (Some(_), None, _) => file,
+        (None, _, Some(dir)) if Path::new(&file).is_relative() => {
+            dir.join(file).to_string_lossy().into_owned()
+        }
+        (None, _, _) => file,
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let config: Config =
+            toml::from_str(s).map_err(|e| Error::UnderlyingError(format!("config: {e}")))?;
+        // fail early on invalid hours rather than when reporting
+        config.workday.apply(Workday::default())?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config: Config = r#"
+file = "~/clocking.db"
+
+[report]
+view = "weekly-detail"
+week_start = "Sun"
+
+[workday]
+hours = "09:00-17:00"
+sat = "off"
+sun = "off"
+
+[server]
+port = 8000
+
+[limits]
+recent_titles = 9
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(config.file.as_deref(), Some("~/clocking.db"));
+        assert_eq!(config.report.view, ReportView::WeeklyDetail);
+        assert_eq!(config.report.week_start, Weekday::Sun);
+        assert_eq!(config.server.port, 8000);
+        assert_eq!(config.server.addr, ServerConfig::default().addr);
+        assert_eq!(config.limits.recent_titles, 9);
+        assert_eq!(config.limits.api_unfinished, 10);
+
+        let workday = config.workday.apply(Workday::default()).unwrap();
+        assert_eq!(
+            workday.to_string(),
+            "09:00-17:00,09:00-17:00,09:00-17:00,09:00-17:00,09:00-17:00,off,off"
+        );
+        assert_eq!(config.workday.overridden(), WEEKDAYS.to_vec());
+        let weekend: Config = "[workday]\nsat = \"off\"".parse().unwrap();
+        assert_eq!(weekend.workday.overridden(), vec![Weekday::Sat]);
+        assert_eq!(config.to_toml().parse::<Config>(), Ok(config));
+
+        assert_eq!("".parse::<Config>(), Ok(Config::default()));
+        assert!("port = 8000".parse::<Config>().is_err(), "unknown key");
+        assert!("[workday]\nmon = \"17:00-09:00\""
+            .parse::<Config>()
+            .is_err());
+    }
+
+    #[test]
+    fn resolve_file_path() {
+        let home = Some(Path::new("/home/me"));
+        let dir = Some(Path::new("/home/me/.config/clocking"));
+        let resolve = |file: &str, home, dir| resolve_file(file.to_string(), home, dir);
+        assert_eq!(resolve("~/clocking.db", home, dir), "/home/me/clocking.db");
+        assert_eq!(resolve("~/clocking.db", None, dir), "~/clocking.db");
+        assert_eq!(resolve("/data/clocking.db", home, dir), "/data/clocking.db");
+        assert_eq!(
+            resolve("~other/clocking.db", home, dir),
+            "/home/me/.config/clocking/~other/clocking.db"
+        );
+        assert_eq!(
+            resolve("clocking.db", home, dir),
+            "/home/me/.config/clocking/clocking.db"
+        );
+        assert_eq!(resolve("clocking.db", home, None), "clocking.db");
+    }
+}
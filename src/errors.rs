@@ -9,10 +9,14 @@ pub enum Error {
     ImpossibleState(String),
     /// User input is invald.
     InvalidInput(&'static str),
+    /// Filter expression is invalid.
+    InvalidFilter(String),
     /// Unfinished entry exists when trying to start a new one.
     UnfinishedExists(String),
     /// Entry with the same title and exact start time already exists.
     DuplicateEntry,
+    /// Storage schema version (found, supported) is newer than this version supports.
+    SchemaTooNew(u32, u32),
 }
 
 impl fmt::Display for Error {
@@ -23,6 +27,7 @@ impl fmt::Display for Error {
             }
             Error::ImpossibleState(err) => writeln!(f, "While, this should never happen: {err}"),
             Error::InvalidInput(err) => writeln!(f, "Input invalid: {err}"),
+            Error::InvalidFilter(err) => writeln!(f, "Filter invalid: {err}"),
             Error::UnfinishedExists(title) => writeln!(
                 f,
                 "Starting new entry is not allowed when there is unfinished entry: {title}"
@@ -30,6 +35,10 @@ impl fmt::Display for Error {
             Error::DuplicateEntry => {
                 writeln!(f, "An entry with the same title and start already exists.")
             }
+            Error::SchemaTooNew(found, supported) => writeln!(
+                f,
+                "Storage schema version {found} is newer than supported version {supported}, please upgrade."
+            ),
         }
     }
 }
@@ -41,3 +50,9 @@ impl From<rusqlite::Error> for Error {
         Error::UnderlyingError(err.to_string())
     }
 }
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Error {
+        Error::UnderlyingError(err.to_string())
+    }
+}
@@ -0,0 +1,310 @@
+//! Export and import finished entries in formats other than the storage.
+//!
+//! Entries can be exported as csv by [`write_csv`] or as iCalendar by [`write_ics`].
+//! History of other time trackers can be imported by [`Timewarrior`], [`Toggl`] and [`Org`],
+//! see [`Importer`].
+use crate::errors::Error;
+use crate::types::{EntryId, FinishedEntry, Pause};
+use crate::Result;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+mod ics;
+mod org;
+mod timewarrior;
+mod toggl;
+
+pub use ics::write_ics;
+pub use org::Org;
+pub use timewarrior::Timewarrior;
+pub use toggl::Toggl;
+
+/// Parse entries from data of a format, to be added by [`crate::ClockingStore::import_finished`].
+pub trait Importer {
+    /// Parse all of `reader`, rows which can not be mapped onto finished entries are skipped.
+    fn parse<'a>(&self, reader: &mut dyn io::Read) -> Result<Parsed<'a>>;
+}
+
+/// Entries parsed by an [`Importer`].
+#[derive(PartialEq, Debug, Default)]
+pub struct Parsed<'a> {
+    pub entries: Vec<FinishedEntry<'a>>,
+    /// Descriptions of skipped rows with reasons, e.g. `line 3: still running`.
+    pub skipped: Vec<String>,
+}
+
+impl<'a> Parsed<'a> {
+    /// Add `entry` parsed at `location` like `line 3`, or skip it with the reason if it failed to
+    /// parse or would be rejected by the store, so that one bad row does not fail the import.
+    fn push(&mut self, location: String, entry: std::result::Result<FinishedEntry<'a>, String>) {
+        let entry = entry.and_then(|entry| {
+            if entry.end <= entry.id.start {
+                return Err("end is not after start".to_string());
+            }
+            match crate::store_helper::validate_pauses(&entry.pauses, &entry.id.start, &entry.end)
+                .and_then(|_| crate::store_helper::validate_tags(&entry.tags))
+            {
+                Err(Error::InvalidInput(reason)) => Err(reason.to_string()),
+                _ => Ok(entry),
+            }
+        });
+        match entry {
+            Ok(entry) => self.entries.push(entry),
+            Err(reason) => self.skipped.push(format!("{location}: {reason}")),
+        }
+    }
+}
+
+/// Where tags of other trackers go, they are often used as what titles are here.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum TagsInto {
+    /// Appended to the title separated by spaces.
+    #[default]
+    Title,
+    /// Tags of the entry, whitespaces in tags are replaced by `-`.
+    Tags,
+}
+
+impl TagsInto {
+    /// Apply tags of an entry titled `title`, returns the title and tags to save.
+    fn apply(self, title: &str, tags: Vec<String>) -> (String, Vec<String>) {
+        match self {
+            TagsInto::Title => {
+                let title = std::iter::once(title)
+                    .chain(tags.iter().map(String::as_str))
+                    .filter(|t| !t.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (title, vec![])
+            }
+            TagsInto::Tags => {
+                // words of tags are joined to be referable by filter term `tag:name`
+                let tags = tags
+                    .iter()
+                    .map(|t| {
+                        t.split(crate::store_helper::is_tag_breaking)
+                            .filter(|word| !word.is_empty())
+                            .collect::<Vec<_>>()
+                            .join("-")
+                    })
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                (title.to_string(), tags)
+            }
+        }
+    }
+}
+
+/// The csv format written by [`write_csv`].
+pub struct Csv;
+
+impl Importer for Csv {
+    fn parse<'a>(&self, reader: &mut dyn io::Read) -> Result<Parsed<'a>> {
+        let mut parsed = Parsed::default();
+        for (i, entry) in read_csv(reader)?.into_iter().enumerate() {
+            // the header is row 1
+            parsed.push(format!("row {}", i + 2), Ok(entry));
+        }
+        Ok(parsed)
+    }
+}
+
+const CSV_HEADER: [&str; 6] = ["title", "start", "end", "notes", "tags", "pauses"];
+const INVALID_PAUSES: &str = "pauses should be space separated 'start/end' pairs in RFC 3339";
+
+/// A csv row, tags and pauses are separated by spaces since neither contains any.
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    title: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    tags: String,
+    /// `start/end` pairs, `end` is empty for an open pause.
+    #[serde(default)]
+    pauses: String,
+}
+
+/// Write `entries` as csv with header `title,start,end,notes,tags,pauses`, times are in RFC 3339.
+pub fn write_csv<W: io::Write>(writer: W, entries: &[FinishedEntry]) -> Result<()> {
+    // header is written explicitly so that it's there even if `entries` is empty
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(writer);
+    writer.write_record(CSV_HEADER)?;
+    for entry in entries.iter() {
+        let pauses: Vec<String> = entry
+            .pauses
+            .iter()
+            .map(|p| {
+                let end = p.end.map(|end| end.to_rfc3339()).unwrap_or_default();
+                format!("{}/{end}", p.start.to_rfc3339())
+            })
+            .collect();
+        writer.serialize(CsvRecord {
+            title: entry.id.title.to_string(),
+            start: entry.id.start,
+            end: entry.end,
+            notes: entry.notes.to_string(),
+            tags: entry.tags.join(" "),
+            pauses: pauses.join(" "),
+        })?;
+    }
+    writer
+        .flush()
+        .map_err(|e| Error::UnderlyingError(e.to_string()))
+}
+
+/// Read entries written by [`write_csv`], columns `notes`, `tags` and `pauses` are optional.
+pub fn read_csv<'a, R: io::Read>(reader: R) -> Result<Vec<FinishedEntry<'a>>> {
+    csv::Reader::from_reader(reader)
+        .deserialize()
+        .map(|record| {
+            let record: CsvRecord = record?;
+            let entry = FinishedEntry {
+                id: EntryId {
+                    title: record.title.into(),
+                    start: record.start,
+                },
+                end: record.end,
+                notes: record.notes.into(),
+                tags: record.tags.split_whitespace().map(String::from).collect(),
+                pauses: record
+                    .pauses
+                    .split_whitespace()
+                    .map(parse_pause)
+                    .collect::<Result<_>>()?,
+            };
+            crate::store_helper::validate_pauses(&entry.pauses, &entry.id.start, &entry.end)?;
+            Ok(entry)
+        })
+        .collect()
+}
+
+fn parse_pause(pause: &str) -> Result<Pause> {
+    let parse = |t: &str| {
+        DateTime::parse_from_rfc3339(t)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|_| Error::InvalidInput(INVALID_PAUSES))
+    };
+    match pause.split_once('/') {
+        Some((start, "")) => Ok(Pause {
+            start: parse(start)?,
+            end: None,
+        }),
+        Some((start, end)) => Ok(Pause {
+            start: parse(start)?,
+            end: Some(parse(end)?),
+        }),
+        None => Err(Error::InvalidInput(INVALID_PAUSES)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_sqlite_store, ClockingStore};
+    use chrono::Duration;
+
+    fn entries() -> Vec<FinishedEntry<'static>> {
+        let start = Utc.with_ymd_and_hms(2023, 1, 31, 9, 0, 0).unwrap();
+        vec![
+            FinishedEntry {
+                id: EntryId {
+                    title: "Coding, \"quoted\"".into(),
+                    start,
+                },
+                end: start + Duration::hours(2),
+                notes: "line 1\nline 2".into(),
+                tags: vec!["rust".to_string(), "work".to_string()],
+                pauses: vec![Pause {
+                    start: start + Duration::minutes(30),
+                    end: Some(start + Duration::minutes(45)),
+                }],
+            },
+            FinishedEntry {
+                id: EntryId {
+                    title: "Reading".into(),
+                    start: start + Duration::hours(3),
+                },
+                end: start + Duration::hours(4),
+                notes: "".into(),
+                tags: vec![],
+                pauses: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let entries = entries();
+        let mut buf = vec![];
+        write_csv(&mut buf, &entries).unwrap();
+        assert!(buf.starts_with(b"title,start,end,notes,tags,pauses\n"));
+        assert_eq!(read_csv(buf.as_slice()).unwrap(), entries);
+
+        buf.clear();
+        write_csv(&mut buf, &[]).unwrap();
+        assert_eq!(buf, b"title,start,end,notes,tags,pauses\n");
+        assert_eq!(read_csv(buf.as_slice()).unwrap(), vec![]);
+
+        let minimal = "title,start,end\nReading,2023-01-31T12:00:00Z,2023-01-31T13:00:00+00:00\n";
+        assert_eq!(read_csv(minimal.as_bytes()).unwrap(), entries[1..]);
+        let zero = format!("{minimal}Zero,2023-01-31T14:00:00Z,2023-01-31T14:00:00Z\n");
+        let parsed = Csv.parse(&mut zero.as_bytes()).unwrap();
+        assert_eq!(parsed.entries, entries[1..]);
+        assert_eq!(parsed.skipped, vec!["row 3: end is not after start"]);
+
+        let bad =
+            "title,start,end,pauses\nReading,2023-01-31T12:00:00Z,2023-01-31T13:00:00Z,12:30\n";
+        assert_eq!(
+            read_csv(bad.as_bytes()),
+            Err(Error::InvalidInput(INVALID_PAUSES))
+        );
+        let outside = "title,start,end,pauses\nReading,2023-01-31T12:00:00Z,2023-01-31T13:00:00Z,2023-01-31T12:30:00Z/2023-01-31T13:30:00Z\n";
+        assert_eq!(
+            read_csv(outside.as_bytes()),
+            Err(Error::InvalidInput("pause must be within the entry"))
+        );
+    }
+
+    #[test]
+    fn import_dedupes() {
+        let entries = entries();
+        let mut store = new_sqlite_store(":memory:").unwrap();
+        store.add_finished_entry(&entries[0]).unwrap();
+
+        let mut buf = vec![];
+        write_csv(&mut buf, &entries).unwrap();
+        let mut imported = read_csv(buf.as_slice()).unwrap();
+        imported.push(entries[1].clone());
+
+        let report = store.import_finished(&imported, true).unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(
+            report.duplicates,
+            vec![entries[0].id.clone(), entries[1].id.clone()]
+        );
+        let all = |store: &dyn ClockingStore| {
+            store
+                .finished(&(entries[0].id.start - Duration::days(1)), None, None)
+                .unwrap()
+        };
+        assert_eq!(all(&store).len(), 1, "dry run saves nothing");
+
+        let report = store.import_finished(&imported, false).unwrap();
+        assert!(!report.dry_run);
+        assert_eq!(report.imported, 1);
+        assert_eq!(all(&store), entries);
+
+        let mut invalid = entries[1].clone();
+        invalid.id.start = invalid.end;
+        assert_eq!(
+            store.import_finished(&[invalid], false),
+            Err(Error::InvalidInput("end must be after start"))
+        );
+    }
+}
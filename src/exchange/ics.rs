@@ -0,0 +1,147 @@
+//! iCalendar of finished entries, see RFC 5545.
+use crate::errors::Error;
+use crate::types::FinishedEntry;
+use crate::Result;
+use std::io;
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+/// Lines longer than this many octets are folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Write `entries` as a calendar of events, titles are summaries and notes are descriptions.
+///
+/// Uids are derived from entry ids so that calendar subscriptions can follow changes.
+pub fn write_ics<W: io::Write>(mut writer: W, entries: &[FinishedEntry]) -> Result<()> {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!(
+            "PRODID:-//clocking//clocking {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    ];
+    for entry in entries.iter() {
+        let start = entry.id.start.format(TIME_FORMAT);
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{start}-{:016x}@clocking",
+            fnv1a(entry.id.title.as_bytes())
+        ));
+        // entries are not versioned, their end is the best guess when they were last changed
+        lines.push(format!("DTSTAMP:{}", entry.end.format(TIME_FORMAT)));
+        lines.push(format!("DTSTART:{start}"));
+        lines.push(format!("DTEND:{}", entry.end.format(TIME_FORMAT)));
+        lines.push(format!("SUMMARY:{}", escape(&entry.id.title)));
+        if !entry.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&entry.notes)));
+        }
+        if !entry.tags.is_empty() {
+            let tags: Vec<String> = entry.tags.iter().map(|t| escape(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .try_for_each(|line| write!(writer, "{}\r\n", fold(line)))
+        .map_err(|e| Error::UnderlyingError(e.to_string()))
+}
+
+/// Escape a text value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold `line` into lines of at most [`MAX_LINE_OCTETS`] octets, without splitting characters.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // the leading space counts
+            octets = 1;
+        }
+        octets += c.len_utf8();
+        folded.push(c);
+    }
+    folded
+}
+
+/// FNV-1a hash, unlike [`std::hash::DefaultHasher`] it's stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EntryId;
+    use chrono::prelude::*;
+
+    #[test]
+    fn events() {
+        let start = Utc.with_ymd_and_hms(2023, 1, 31, 9, 0, 0).unwrap();
+        let entry = FinishedEntry {
+            id: EntryId {
+                title: "Review, plan; ship".into(),
+                start,
+            },
+            end: start + chrono::Duration::hours(1),
+            notes: "first\nsecond".into(),
+            tags: vec!["work".to_string(), "team".to_string()],
+            pauses: vec![],
+        };
+        let mut buf = vec![];
+        write_ics(&mut buf, &[entry]).unwrap();
+        let ics = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+        assert_eq!(lines[0], "BEGIN:VCALENDAR");
+        assert_eq!(
+            lines[3..]
+                .iter()
+                .filter(|l| !l.starts_with("UID:"))
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![
+                "BEGIN:VEVENT",
+                "DTSTAMP:20230131T100000Z",
+                "DTSTART:20230131T090000Z",
+                "DTEND:20230131T100000Z",
+                "SUMMARY:Review\\, plan\\; ship",
+                "DESCRIPTION:first\\nsecond",
+                "CATEGORIES:work,team",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+        );
+        assert!(lines[4].starts_with("UID:20230131T090000Z-"));
+
+        let long = "é".repeat(50);
+        let folded = fold(&format!("SUMMARY:{long}"));
+        let folded: Vec<&str> = folded.split("\r\n").collect();
+        assert!(folded.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert_eq!(folded.len(), 2);
+        assert_eq!(
+            folded.concat().replacen(" ", "", 1),
+            format!("SUMMARY:{long}")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Clock lines of org-mode files.
+use super::{Importer, Parsed, TagsInto};
+use crate::timeparse::{from_local, parse_time};
+use crate::types::{EntryId, FinishedEntry};
+use crate::Result;
+use chrono::prelude::*;
+use regex::Regex;
+use std::io;
+
+const TODO_KEYWORDS: &[&str] = &["TODO", "NEXT", "WAITING", "DONE", "CANCELED", "CANCELLED"];
+
+/// Import `CLOCK: [2023-01-31 Tue 09:00]--[2023-01-31 Tue 11:00] =>  2:00` lines of org files,
+/// times are in local timezone.
+///
+/// The heading a clock line belongs to is the title, without its todo keyword and priority.
+/// Tags of the heading like `:work:rust:` are handled by `tags_into`, running clocks are skipped.
+#[derive(Default)]
+pub struct Org {
+    pub tags_into: TagsInto,
+}
+
+impl Importer for Org {
+    fn parse<'a>(&self, reader: &mut dyn io::Read) -> Result<Parsed<'a>> {
+        let mut data = String::new();
+        reader
+            .read_to_string(&mut data)
+            .map_err(|e| crate::errors::Error::UnderlyingError(e.to_string()))?;
+
+        let clock = Regex::new(r"^\s*CLOCK:\s*\[([^\]]*)\](?:--\[([^\]]*)\])?").unwrap();
+        let mut heading: Option<(String, Vec<String>)> = None;
+        let mut parsed = Parsed::default();
+        for (i, line) in data.lines().enumerate() {
+            if let Some(h) = parse_heading(line) {
+                heading = Some(h);
+                continue;
+            }
+            let Some(caps) = clock.captures(line) else {
+                continue;
+            };
+            let entry = match (&heading, caps.get(2)) {
+                (None, _) => Err("no heading for the title"),
+                (Some(_), None) => Err("still running"),
+                (Some((title, tags)), Some(end)) => {
+                    let (title, tags) = self.tags_into.apply(title, tags.clone());
+                    parse_timestamp(&caps[1])
+                        .and_then(|start| Ok((start, parse_timestamp(end.as_str())?)))
+                        .map(|(start, end)| FinishedEntry {
+                            id: EntryId {
+                                title: title.into(),
+                                start,
+                            },
+                            end,
+                            notes: "".into(),
+                            tags,
+                            pauses: vec![],
+                        })
+                }
+            };
+            parsed.push(format!("line {}", i + 1), entry.map_err(String::from));
+        }
+        Ok(parsed)
+    }
+}
+
+/// Parse title and tags of `* TODO [#A] Title :tag1:tag2:`.
+fn parse_heading(line: &str) -> Option<(String, Vec<String>)> {
+    let text = line.trim_start_matches('*');
+    if text.len() == line.len() || !text.starts_with(' ') {
+        return None;
+    }
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let mut tags = vec![];
+    if let Some(last) = words.last() {
+        if last.len() > 2 && last.starts_with(':') && last.ends_with(':') {
+            tags = last
+                .split(':')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect();
+            words.pop();
+        }
+    }
+    if words.first().is_some_and(|w| TODO_KEYWORDS.contains(w)) {
+        words.remove(0);
+    }
+    if words
+        .first()
+        .is_some_and(|w| w.starts_with("[#") && w.ends_with(']'))
+    {
+        words.remove(0);
+    }
+    Some((words.join(" "), tags))
+}
+
+/// Parse the inside of `[2023-01-31 Tue 09:00]`, the day name is ignored.
+fn parse_timestamp(input: &str) -> std::result::Result<DateTime<Utc>, &'static str> {
+    let mut words = input.split_whitespace();
+    let date = words
+        .next()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or("invalid date")?;
+    let time = words.last().and_then(parse_time).ok_or("invalid time")?;
+    from_local(&date.and_time(time), &Local).map_err(|_| "time does not exist in local timezone")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_clock_lines() {
+        let data = "\
+CLOCK: [2023-01-30 Mon 09:00]--[2023-01-30 Mon 10:00] =>  1:00
+* Projects
+** TODO [#A] Write report :work:docs:
+   :LOGBOOK:
+   CLOCK: [2023-01-31 Tue 09:00]--[2023-01-31 Tue 11:30] =>  2:30
+   CLOCK: [2023-01-31 Tue 13:00]
+   CLOCK: [2023-01-31 Tue 14:00]--[2023-01-31 Tue 1500] =>  1:00
+   :END:
+** Reading
+CLOCK: [2023-02-01 Wed 20:00]--[2023-02-01 Wed 21:00] =>  1:00
+CLOCK: [2023-02-01 Wed 22:00]--[2023-02-01 Wed 22:00] =>  0:00
+";
+        let parsed = Org::default().parse(&mut data.as_bytes()).unwrap();
+        let titles: Vec<&str> = parsed.entries.iter().map(|e| e.id.title.as_ref()).collect();
+        assert_eq!(titles, vec!["Write report work docs", "Reading"]);
+        let first = &parsed.entries[0];
+        assert_eq!(
+            first.id.start,
+            Local
+                .with_ymd_and_hms(2023, 1, 31, 9, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        );
+        assert_eq!(first.end - first.id.start, chrono::Duration::minutes(150));
+        assert_eq!(
+            parsed.skipped,
+            vec![
+                "line 1: no heading for the title",
+                "line 6: still running",
+                "line 7: invalid time",
+                "line 11: end is not after start",
+            ]
+        );
+
+        let parsed = Org {
+            tags_into: TagsInto::Tags,
+        }
+        .parse(&mut data.as_bytes())
+        .unwrap();
+        assert_eq!(parsed.entries[0].id.title, "Write report");
+        assert_eq!(parsed.entries[0].tags, vec!["work", "docs"]);
+    }
+}
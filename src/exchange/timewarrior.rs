@@ -0,0 +1,159 @@
+//! Data files of timewarrior, `~/.timewarrior/data/YYYY-MM.data`.
+use super::{Importer, Parsed, TagsInto};
+use crate::types::{EntryId, FinishedEntry};
+use crate::Result;
+use chrono::prelude::*;
+use std::io;
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Import intervals of timewarrior, lines like
+/// `inc 20230131T090000Z - 20230131T110000Z # tag "another tag" # "annotation"`.
+///
+/// Timewarrior has tags only, they make up the title, or with [`TagsInto::Tags`] the first tag is
+/// the title and the rest are tags. Annotations become notes, open intervals are skipped.
+#[derive(Default)]
+pub struct Timewarrior {
+    pub tags_into: TagsInto,
+}
+
+impl Importer for Timewarrior {
+    fn parse<'a>(&self, reader: &mut dyn io::Read) -> Result<Parsed<'a>> {
+        let mut data = String::new();
+        reader
+            .read_to_string(&mut data)
+            .map_err(|e| crate::errors::Error::UnderlyingError(e.to_string()))?;
+
+        let mut parsed = Parsed::default();
+        for (i, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = self.parse_line(line).map_err(String::from);
+            parsed.push(format!("line {}", i + 1), entry);
+        }
+        Ok(parsed)
+    }
+}
+
+impl Timewarrior {
+    fn parse_line<'a>(&self, line: &str) -> std::result::Result<FinishedEntry<'a>, &'static str> {
+        let line = line.trim().strip_prefix("inc ").ok_or("not an interval")?;
+        let (range, rest) = line.split_once('#').unwrap_or((line, ""));
+        let (start, end) = match range.split_once(" - ") {
+            Some((start, end)) => (parse_time(start)?, parse_time(end)?),
+            None => {
+                parse_time(range)?;
+                return Err("still running");
+            }
+        };
+
+        let mut tokens = tokenize(rest).into_iter();
+        let tags: Vec<String> = tokens.by_ref().take_while(|t| t != "#").collect();
+        let notes = tokens.collect::<Vec<_>>().join(" ");
+        let (title, tags) = match self.tags_into {
+            TagsInto::Title => self.tags_into.apply("", tags),
+            TagsInto::Tags => {
+                let mut tags = tags.into_iter();
+                let title = tags.next().unwrap_or_default();
+                self.tags_into.apply(&title, tags.collect())
+            }
+        };
+        if title.is_empty() {
+            return Err("no tags for the title");
+        }
+
+        Ok(FinishedEntry {
+            id: EntryId {
+                title: title.into(),
+                start,
+            },
+            end,
+            notes: notes.into(),
+            tags,
+            pauses: vec![],
+        })
+    }
+}
+
+fn parse_time(input: &str) -> std::result::Result<DateTime<Utc>, &'static str> {
+    NaiveDateTime::parse_from_str(input.trim(), TIME_FORMAT)
+        .map(|t| Utc.from_utc_datetime(&t))
+        .map_err(|_| "invalid time")
+}
+
+/// Split by whitespaces, except in double quotes where `\"` and `\\` are escaped.
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => token.extend(chars.next()),
+                    c => token.push(c),
+                }
+            }
+        } else {
+            token.push(c);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_intervals() {
+        let data = r#"inc 20230131T090000Z - 20230131T110000Z # Coding "clocking \"cli\"" # "fix bug"
+inc 20230131T120000Z - 20230131T123000Z # Lunch
+
+inc 20230131T130000Z - 20230131T140000Z
+something else
+inc 20230131T150000Z # Reading
+inc 20230131T160000Z - 20230131T160000Z # Zero
+"#;
+        let parsed = Timewarrior::default().parse(&mut data.as_bytes()).unwrap();
+        let titles: Vec<&str> = parsed.entries.iter().map(|e| e.id.title.as_ref()).collect();
+        assert_eq!(titles, vec!["Coding clocking \"cli\"", "Lunch"]);
+        let coding = &parsed.entries[0];
+        assert_eq!(
+            coding.id.start,
+            Utc.with_ymd_and_hms(2023, 1, 31, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            coding.end,
+            Utc.with_ymd_and_hms(2023, 1, 31, 11, 0, 0).unwrap()
+        );
+        assert_eq!(coding.notes, "fix bug");
+        assert_eq!(
+            parsed.skipped,
+            vec![
+                "line 4: no tags for the title",
+                "line 5: not an interval",
+                "line 6: still running",
+                "line 7: end is not after start",
+            ]
+        );
+
+        let parsed = Timewarrior {
+            tags_into: TagsInto::Tags,
+        }
+        .parse(&mut data.as_bytes())
+        .unwrap();
+        assert_eq!(parsed.entries[0].id.title, "Coding");
+        assert_eq!(parsed.entries[0].tags, vec!["clocking-cli"]);
+        assert!(parsed.entries[1].tags.is_empty());
+    }
+}
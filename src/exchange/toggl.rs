@@ -0,0 +1,138 @@
+//! Detailed report csv exported by Toggl Track.
+use super::{Importer, Parsed, TagsInto};
+use crate::timeparse::{from_local, parse_time};
+use crate::types::{EntryId, FinishedEntry};
+use crate::Result;
+use chrono::prelude::*;
+use serde::Deserialize;
+use std::io;
+
+/// Columns used, others like `User` and `Billable` are ignored.
+#[derive(Deserialize)]
+struct TogglRecord {
+    #[serde(rename = "Project", default)]
+    project: String,
+    #[serde(rename = "Description", default)]
+    description: String,
+    #[serde(rename = "Start date")]
+    start_date: String,
+    #[serde(rename = "Start time")]
+    start_time: String,
+    #[serde(rename = "End date")]
+    end_date: String,
+    #[serde(rename = "End time")]
+    end_time: String,
+    /// Separated by `, `.
+    #[serde(rename = "Tags", default)]
+    tags: String,
+}
+
+/// Import time entries of Toggl Track's detailed csv export, times are in local timezone.
+///
+/// The project is the title and the description becomes notes, or the description is the
+/// title of entries without a project.
+#[derive(Default)]
+pub struct Toggl {
+    pub tags_into: TagsInto,
+}
+
+impl Importer for Toggl {
+    fn parse<'a>(&self, reader: &mut dyn io::Read) -> Result<Parsed<'a>> {
+        let mut parsed = Parsed::default();
+        for (i, record) in csv::Reader::from_reader(reader).deserialize().enumerate() {
+            // the header is row 1
+            let row = i + 2;
+            let entry = record.map_err(|e| e.to_string()).and_then(|r| self.map(r));
+            parsed.push(format!("row {row}"), entry);
+        }
+        Ok(parsed)
+    }
+}
+
+impl Toggl {
+    fn map<'a>(&self, record: TogglRecord) -> std::result::Result<FinishedEntry<'a>, String> {
+        let start = local_time(&record.start_date, &record.start_time)?;
+        let end = local_time(&record.end_date, &record.end_time)?;
+        let (title, notes) = if record.project.is_empty() {
+            (record.description, String::new())
+        } else {
+            (record.project, record.description)
+        };
+        if title.is_empty() {
+            return Err("neither project nor description".to_string());
+        }
+        let tags = record
+            .tags
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+        let (title, tags) = self.tags_into.apply(&title, tags);
+
+        Ok(FinishedEntry {
+            id: EntryId {
+                title: title.into(),
+                start,
+            },
+            end,
+            notes: notes.into(),
+            tags,
+            pauses: vec![],
+        })
+    }
+}
+
+fn local_time(date: &str, time: &str) -> std::result::Result<DateTime<Utc>, String> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{date}'"))?;
+    let time = parse_time(time).ok_or_else(|| format!("invalid time '{time}'"))?;
+    from_local(&date.and_time(time), &Local).map_err(|e| e.to_string().trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_detailed_report() {
+        let data = "\
+User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()
+me,me@example.com,,Clocking,,Import history,No,2023-01-31,09:00:00,2023-01-31,11:30:00,02:30:00,\"rust, side project\",
+me,me@example.com,,,,Reading,No,2023-01-31,12:00:00,2023-01-31,13:00:00,01:00:00,,
+me,me@example.com,,,,,No,2023-01-31,14:00:00,2023-01-31,15:00:00,01:00:00,,
+me,me@example.com,,Clocking,,,No,2023-01-31,9am,2023-01-31,10:00:00,01:00:00,,
+me,me@example.com,,Clocking,,Zero,No,2023-01-31,16:00:00,2023-01-31,16:00:00,00:00:00,,
+";
+        let parsed = Toggl::default().parse(&mut data.as_bytes()).unwrap();
+        let titles: Vec<&str> = parsed.entries.iter().map(|e| e.id.title.as_ref()).collect();
+        assert_eq!(titles, vec!["Clocking rust side project", "Reading"]);
+        let first = &parsed.entries[0];
+        assert_eq!(first.notes, "Import history");
+        assert_eq!(first.end - first.id.start, chrono::Duration::minutes(150));
+        assert_eq!(
+            first.id.start,
+            Local
+                .with_ymd_and_hms(2023, 1, 31, 9, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        );
+        assert_eq!(parsed.entries[1].notes, "");
+        assert_eq!(
+            parsed.skipped,
+            vec![
+                "row 4: neither project nor description",
+                "row 5: invalid time '9am'",
+                "row 6: end is not after start",
+            ]
+        );
+
+        let parsed = Toggl {
+            tags_into: TagsInto::Tags,
+        }
+        .parse(&mut data.as_bytes())
+        .unwrap();
+        assert_eq!(parsed.entries[0].id.title, "Clocking");
+        assert_eq!(parsed.entries[0].tags, vec!["rust", "side-project"]);
+    }
+}
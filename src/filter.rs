@@ -0,0 +1,367 @@
+//! A small filter expression language on entry titles and tags.
+//!
+//! Terms:
+//!   - `word` or `"quoted words"`: title contains the text.
+//!   - `word*`, `wo?d`, `[wW]ord`: glob on the whole title (`*`, `?` and `[...]`).
+//!   - `/regex/`: title matches the regular expression.
+//!   - `tag:name`: entry is tagged by `name`.
+//!
+//! Terms can be combined by `and`, `or`, `not` and parentheses, adjacent terms
+//! are implicitly joined by `and`. All matches are case sensitive.
+use crate::errors::Error;
+use regex::Regex;
+use std::str::FromStr;
+
+/// Compiled filter expression.
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Contains(String),
+    Glob(String, Regex),
+    Regex(Regex),
+    Tag(String),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Test the filter against an entry's title and tags.
+    pub fn matches(&self, title: &str, tags: &[String]) -> bool {
+        match self {
+            Filter::Contains(s) => title.contains(s.as_str()),
+            Filter::Glob(_, re) | Filter::Regex(re) => re.is_match(title),
+            Filter::Tag(tag) => tags.contains(tag),
+            Filter::And(l, r) => l.matches(title, tags) && r.matches(title, tags),
+            Filter::Or(l, r) => l.matches(title, tags) || r.matches(title, tags),
+            Filter::Not(f) => !f.matches(title, tags),
+        }
+    }
+
+    /// Combine `filter` with `tag:` terms, matches entries having all `tags`.
+    pub fn with_tags(filter: Option<Filter>, tags: &[String]) -> Option<Filter> {
+        tags.iter()
+            .map(|t| Filter::Tag(t.clone()))
+            .fold(filter, |acc, tag_filter| match acc {
+                Some(f) => Some(Filter::And(Box::new(f), Box::new(tag_filter))),
+                None => Some(tag_filter),
+            })
+    }
+
+    /// Translate the filter to an SQL boolean expression on table `clocking`,
+    /// values are pushed to `params` in placeholder order.
+    ///
+    /// Regular expressions require a `regexp(pattern, text)` function registered on the connection.
+    pub(crate) fn to_sql(&self, params: &mut Vec<String>) -> String {
+        match self {
+            Filter::Contains(s) => {
+                params.push(s.clone());
+                "instr(title, ?) > 0".to_string()
+            }
+            Filter::Glob(pattern, _) => {
+                params.push(pattern.clone());
+                "title GLOB ?".to_string()
+            }
+            Filter::Regex(re) => {
+                params.push(re.as_str().to_string());
+                "title REGEXP ?".to_string()
+            }
+            Filter::Tag(tag) => {
+                params.push(tag.clone());
+                "clocking.id IN (SELECT clocking_id FROM clocking_tag JOIN tag ON tag.id = clocking_tag.tag_id WHERE tag.name = ?)".to_string()
+            }
+            Filter::And(l, r) => format!("({} AND {})", l.to_sql(params), r.to_sql(params)),
+            Filter::Or(l, r) => format!("({} OR {})", l.to_sql(params), r.to_sql(params)),
+            Filter::Not(f) => format!("(NOT {})", f.to_sql(params)),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = FilterParser { tokens, pos: 0 };
+        let filter = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(Error::InvalidFilter(format!("unexpected {token:?}"))),
+        }
+    }
+}
+
+const TAG_PREFIX: &str = "tag:";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Quoted(String),
+    Regex(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' | '/' => {
+                chars.next();
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some(c2) = chars.next() {
+                    if c2 == '\\' {
+                        match chars.next() {
+                            // keep escapes other than the delimiter for regex
+                            Some(e) if e == c => text.push(e),
+                            Some(e) if c == '/' => {
+                                text.push('\\');
+                                text.push(e);
+                            }
+                            Some(e) => text.push(e),
+                            None => break,
+                        }
+                    } else if c2 == c {
+                        closed = true;
+                        break;
+                    } else {
+                        text.push(c2);
+                    }
+                }
+                if !closed {
+                    return Err(Error::InvalidFilter(format!("missing closing {c}")));
+                }
+                tokens.push(if c == '"' {
+                    Token::Quoted(text)
+                } else {
+                    Token::Regex(text)
+                });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c2) = chars.peek() {
+                    if c2.is_whitespace() || c2 == '(' || c2 == ')' || c2 == '"' {
+                        break;
+                    }
+                    word.push(c2);
+                    chars.next();
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct FilterParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, Error> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            let right = self.parse_and()?;
+            left = Filter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, Error> {
+        let mut left = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // implicit `and` between adjacent terms
+                Some(
+                    Token::Not
+                    | Token::LParen
+                    | Token::Word(_)
+                    | Token::Quoted(_)
+                    | Token::Regex(_),
+                ) => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            left = Filter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, Error> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            Ok(Filter::Not(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Filter, Error> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err(Error::InvalidFilter("missing closing )".to_string())),
+                }
+            }
+            Some(Token::Word(w)) if w.starts_with(TAG_PREFIX) => {
+                match w.trim_start_matches(TAG_PREFIX) {
+                    "" => Err(Error::InvalidFilter("empty tag name".to_string())),
+                    tag => Ok(Filter::Tag(tag.to_string())),
+                }
+            }
+            Some(Token::Word(w)) if w.contains(['*', '?', '[']) => {
+                let re = glob_to_regex(&w)?;
+                Ok(Filter::Glob(w, re))
+            }
+            Some(Token::Word(w)) | Some(Token::Quoted(w)) => Ok(Filter::Contains(w)),
+            Some(Token::Regex(r)) => Regex::new(&r)
+                .map(Filter::Regex)
+                .map_err(|e| Error::InvalidFilter(e.to_string())),
+            Some(token) => Err(Error::InvalidFilter(format!("unexpected {token:?}"))),
+            None => Err(Error::InvalidFilter("unexpected end of filter".to_string())),
+        }
+    }
+}
+
+/// Translate a glob with SQLite `GLOB` semantics to an anchored regex.
+fn glob_to_regex(glob: &str) -> Result<Regex, Error> {
+    let mut re = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' => {
+                re.push('[');
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                    re.push('^');
+                }
+                // a leading `]` is a literal member of the set
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    re.push_str("\\]");
+                }
+                let mut closed = false;
+                for c2 in chars.by_ref() {
+                    if c2 == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c2 == '\\' || c2 == '[' || c2 == '&' || c2 == '~' {
+                        re.push('\\');
+                    }
+                    re.push(c2);
+                }
+                if !closed {
+                    return Err(Error::InvalidFilter(format!("missing closing ] in {glob}")));
+                }
+                re.push(']');
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+
+    Regex::new(&re).map_err(|e| Error::InvalidFilter(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Filter {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn filter_terms() {
+        assert!(parse("clock").matches("clocking: server", &[]));
+        assert!(!parse("Clock").matches("clocking: server", &[]));
+        assert!(parse("\"g: s\"").matches("clocking: server", &[]));
+        assert!(parse("clock*").matches("clocking: server", &[]));
+        assert!(!parse("server*").matches("clocking: server", &[]));
+        assert!(parse("?locking*").matches("clocking: server", &[]));
+        assert!(parse("[bc]lock*").matches("clocking: server", &[]));
+        assert!(parse("/^c.*r$/").matches("clocking: server", &[]));
+        assert!(!parse("/^server/").matches("clocking: server", &[]));
+        assert!(parse(r"/a\/b/").matches("a/b", &[]));
+    }
+
+    #[test]
+    fn filter_operators() {
+        let f = parse("clocking and not (views or /serv/)");
+        assert!(f.matches("clocking: store", &[]));
+        assert!(!f.matches("clocking: views", &[]));
+        assert!(!f.matches("clocking: server", &[]));
+        assert!(!f.matches("reading", &[]));
+
+        let f = parse("reading or clocking views");
+        assert!(f.matches("reading", &[]));
+        assert!(f.matches("clocking: views", &[]));
+        assert!(!f.matches("clocking: server", &[]));
+
+        assert!(parse("NOT reading").matches("clocking", &[]));
+    }
+
+    #[test]
+    fn filter_tags() {
+        let tags = vec!["work".to_string(), "meeting".to_string()];
+        assert!(parse("tag:work").matches("standup", &tags));
+        assert!(!parse("tag:wor").matches("standup", &tags));
+        assert!(parse("tag:work and not tag:reading").matches("standup", &tags));
+        assert!(!parse("tag:work and not standup").matches("standup", &tags));
+
+        let f = Filter::with_tags(Some(parse("stand*")), &tags).unwrap();
+        assert!(f.matches("standup", &tags));
+        assert!(!f.matches("standup", &tags[..1]));
+        assert!(Filter::with_tags(None, &[]).is_none());
+    }
+
+    #[test]
+    fn filter_invalid() {
+        for s in [
+            "", "(a", "a)", "\"a", "/a", "/(/", "a and", "not", "[ab", "tag:",
+        ] {
+            assert!(s.parse::<Filter>().is_err(), "{s} should be invalid");
+        }
+    }
+}
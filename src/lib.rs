@@ -1,31 +1,41 @@
+pub mod config;
 pub mod errors;
+pub mod exchange;
+pub mod filter;
 #[cfg(feature = "http")]
 pub mod server;
 mod sqlite_store;
+pub mod timeparse;
 pub mod types;
 pub mod views;
 
 use chrono::prelude::*;
+use filter::Filter;
 use sqlite_store::SqliteStore;
 use std::borrow::Cow;
 use types::*;
 
 type Result<T> = std::result::Result<T, errors::Error>;
 
-pub fn new_sqlite_store(path: &str) -> impl ClockingStore {
+/// Open or create a sqlite store at `path`, migrating its schema to the latest version.
+pub fn new_sqlite_store(path: &str) -> Result<impl ClockingStore> {
     SqliteStore::new(path)
 }
 
 const NAIVE_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Default of [`ClockingStore::max_entry_length`].
+pub const DEFAULT_MAX_ENTRY_LENGTH_HOURS: i64 = 12;
 pub trait ClockingStore {
     /// Start a clocking entry at now.
-    fn start<'a, 'b>(&'a mut self, title: &'b str) -> Result<EntryId<'b>> {
+    fn start<'b>(&mut self, title: &'b str) -> Result<EntryId<'b>> {
         let entry = UnfinishedEntry {
             id: EntryId {
                 title: Cow::Borrowed(title),
                 start: Utc::now(),
             },
             notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
         };
 
         self.start_entry(&entry)?;
@@ -33,106 +43,551 @@ pub trait ClockingStore {
     }
 
     /// Start a clocking entry.
+    ///
+    /// Fails with [`errors::Error::UnfinishedExists`] if an unfinished entry exists in [`TrackingMode::Single`].
     fn start_entry(&mut self, entry: &UnfinishedEntry) -> Result<()>;
 
-    /// Try to finish the latest-started unfinished entry.
+    /// Start `entry` which may start in the past, e.g. forgot to start clocking in time.
+    ///
+    /// In [`TrackingMode::Single`] the start must not be before the end of any finished entry,
+    /// so starting late never creates overlaps. Starting in the future is rejected.
+    fn start_entry_since(&mut self, entry: &UnfinishedEntry) -> Result<()> {
+        let now = Utc::now();
+        if entry.id.start > now {
+            return Err(errors::Error::InvalidInput(
+                "start must not be in the future",
+            ));
+        }
+        if self.tracking_mode()? == TrackingMode::Single
+            && !self.finished_overlapping(&entry.id.start, &now)?.is_empty()
+        {
+            return Err(errors::Error::InvalidInput(
+                "start must not be before the end of the latest finished entry",
+            ));
+        }
+
+        self.start_entry(entry)
+    }
+
+    /// Finish the ongoing entry and start a new one of `title` at now.
+    ///
+    /// Returns the title of the finished entry, or None if there was no ongoing entry.
+    fn switch_to(&mut self, title: &str, notes: &str) -> Result<Option<String>> {
+        let entry = UnfinishedEntry {
+            id: EntryId {
+                title: Cow::Borrowed(title),
+                start: Utc::now(),
+            },
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+
+        self.switch_to_entry(&entry, notes)
+    }
+
+    /// Finish the ongoing entry at `entry.id.start` with `notes` appended, and start `entry`, atomically.
+    ///
+    /// The latest-started one is finished if multiple entries are ongoing in [`TrackingMode::Parallel`].
+    /// Just starts `entry` if there is no ongoing entry.
+    /// Returns the title of the finished entry, or None if there was no ongoing entry.
+    fn switch_to_entry(&mut self, entry: &UnfinishedEntry, notes: &str) -> Result<Option<String>>;
+
+    /// Current [`TrackingMode`] of the store, [`TrackingMode::Single`] unless changed.
+    fn tracking_mode(&self) -> Result<TrackingMode>;
+
+    /// Change [`TrackingMode`] of the store.
+    ///
+    /// Changing to [`TrackingMode::Single`] fails with [`errors::Error::UnfinishedExists`]
+    /// while more than one unfinished entries exist.
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<()>;
+
+    /// Maximum length of an entry, unfinished entries started longer ago are overdue.
+    ///
+    /// Default to [`DEFAULT_MAX_ENTRY_LENGTH_HOURS`] hours, None if disabled.
+    fn max_entry_length(&self) -> Result<Option<chrono::Duration>>;
+
+    /// Change maximum length of an entry, None to disable overdue detection.
+    fn set_max_entry_length(&mut self, length: Option<chrono::Duration>) -> Result<()>;
+
+    /// Working hours of each weekday, 08:00-21:00 every day unless changed.
+    fn workday(&self) -> Result<Workday>;
+
+    /// Change working hours of each weekday.
+    fn set_workday(&mut self, workday: &Workday) -> Result<()>;
+
+    /// Hourly rates of projects ordered by project, see [`Rate::find`] for which applies to a title.
+    fn rates(&self) -> Result<Vec<Rate>>;
+
+    /// Add or replace the rate of `rate.project`, whose projects are normalized like `acme/website`.
+    fn set_rate(&mut self, rate: &Rate) -> Result<()>;
+
+    /// Remove the rate of `project`, returns false if not found.
+    fn remove_rate(&mut self, project: &str) -> Result<bool>;
+
+    /// Goals ordered by subject, period and kind.
+    fn goals(&self) -> Result<Vec<Goal>>;
+
+    /// Add a goal or replace the duration of the one with the same subject, period and kind.
+    fn set_goal(&mut self, goal: &Goal) -> Result<()>;
+
+    /// Remove the goal of `subject`, `period` and `kind`, returns false if not found.
+    fn remove_goal(
+        &mut self,
+        subject: &GoalSubject,
+        period: GoalPeriod,
+        kind: GoalKind,
+    ) -> Result<bool>;
+
+    /// Fetch unfinished entries started longer than [`ClockingStore::max_entry_length`] ago,
+    /// most likely forgotten to finish.
+    fn overdue<'a>(&self) -> Result<Vec<UnfinishedEntry<'a>>> {
+        match self.max_entry_length()? {
+            Some(length) => match Utc::now().checked_sub_signed(length) {
+                Some(started_before) => Ok(self
+                    .unfinished(usize::MAX)?
+                    .into_iter()
+                    .filter(|entry| entry.id.start < started_before)
+                    .collect()),
+                // nothing could have started that long ago
+                None => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Add an entry finished already, e.g. a meeting happened before.
+    ///
+    /// `entry.end` must be after `entry.id.start`. Overlapping with other entries is not checked,
+    /// see [`ClockingStore::finished_overlapping`].
+    fn add_finished_entry(&mut self, entry: &FinishedEntry) -> Result<()>;
+
+    /// Add finished entries in bulk, e.g. imported from another file.
+    ///
+    /// Entries with the same title and start as an existing one or an earlier one in `entries` are
+    /// skipped and reported instead of failing with [`errors::Error::DuplicateEntry`]. Nothing is added if
+    /// any entry is invalid, see [`ClockingStore::add_finished_entry`], or if `dry_run` is true.
+    fn import_finished<'a>(
+        &mut self,
+        entries: &[FinishedEntry<'a>],
+        dry_run: bool,
+    ) -> Result<ImportReport<'a>>;
+
+    /// Try to finish the latest-started unfinished entry, ending its open pause if paused.
     ///
     /// Returns Ok(None) if no such unfinished entry found.
     fn try_finish_any(&mut self, notes: &str) -> Result<Option<String>>;
 
     /// Try to finish an unfinished clocking entry, set end datetime to `end`.
     ///
+    /// An open pause of the entry is ended at `end` too.
     /// Returns false if give entry is already finished or not found.
     fn try_finish_entry(&mut self, id: &EntryId, end: &DateTime<Utc>, notes: &str) -> Result<bool>;
 
-    /// Query finished clocking entries with start in `[query_start, query_end]`.
+    /// Unfinished entries of `title`, or all of them if `title` is None, latest-started first.
+    ///
+    /// Candidates to choose from when several entries are tracked in parallel.
+    fn unfinished_of<'a>(&self, title: Option<&str>) -> Result<Vec<UnfinishedEntry<'a>>> {
+        Ok(self
+            .unfinished(usize::MAX)?
+            .into_iter()
+            .filter(|entry| title.is_none_or(|t| entry.id.title == t))
+            .collect())
+    }
+
+    /// Pause the only unfinished entry at `at`, returns its title.
+    ///
+    /// Returns Ok(None) if there is no unfinished entry or it's paused already, fails if several
+    /// entries are unfinished, see [`ClockingStore::try_pause_entry`] for those.
+    /// `at` must be after the entry start and its previous pauses.
+    fn try_pause_any(&mut self, at: &DateTime<Utc>) -> Result<Option<String>>;
+
+    /// Resume the only unfinished entry paused, at `at`, returns its title.
     ///
-    /// `query_end` default to now if None is specified.
+    /// Returns Ok(None) if there is no unfinished entry or it's not paused, fails if several
+    /// entries are unfinished, see [`ClockingStore::try_resume_entry`] for those.
+    /// `at` must be after the pause start.
+    fn try_resume_any(&mut self, at: &DateTime<Utc>) -> Result<Option<String>>;
+
+    /// Pause the unfinished entry of `id` at `at`.
+    ///
+    /// Returns Ok(false) if it's not found, finished or paused already.
+    fn try_pause_entry(&mut self, id: &EntryId, at: &DateTime<Utc>) -> Result<bool>;
+
+    /// Resume the paused unfinished entry of `id` at `at`.
+    ///
+    /// Returns Ok(false) if it's not found, finished or not paused.
+    fn try_resume_entry(&mut self, id: &EntryId, at: &DateTime<Utc>) -> Result<bool>;
+
+    /// Query finished clocking entries overlapping `(query_start, query_end)`, ordered by start.
+    ///
+    /// `query_end` default to now if None is specified. Entries are whole, including their time
+    /// out of the range.
+    /// Only entries matched by `filter` are returned if specified.
     fn finished<'a>(
         &self,
         query_start: &DateTime<Utc>,
         query_end: Option<DateTime<Utc>>,
+        filter: Option<&Filter>,
     ) -> Result<Vec<FinishedEntry<'a>>>;
 
     /// Query finished clocking entries from date range:
     ///   start: (@today - `days_offset`) 0:00:00
     ///   to: (@today - `days_offset` + days) 0:00:00 if days is not None, otherwise to now()
+    ///
+    /// Entries running across the range are clipped to it, so that only time within is reported.
     fn finished_by_offset<'a>(
         &self,
         days_offset: u64,
         days: Option<u64>,
+        filter: Option<&Filter>,
     ) -> Result<Vec<FinishedEntry<'a>>> {
         let (start, end) = store_helper::query_start_end(days_offset, days);
-        self.finished(&start, end)
+        let entries = self.finished(&start, end, filter)?;
+        Ok(store_helper::clip(
+            entries,
+            &start,
+            &end.unwrap_or_else(Utc::now),
+        ))
     }
 
     /// Query finished clocking entries, accepts 'yyyy-mm-dd' local dates as query range.
     ///
-    /// Note: `day_end` is included in the query range.
-    fn finished_by_date_str(&self, day_start: &str, day_end: &str) -> Result<Vec<FinishedEntry>> {
-        let start_date = NaiveDate::parse_from_str(day_start, NAIVE_DATE_FORMAT)
-            .map_err(|_| errors::Error::InvalidInput("day_start"))?;
-        let end_date = NaiveDate::parse_from_str(day_end, NAIVE_DATE_FORMAT)
-            .map_err(|_| errors::Error::InvalidInput("day_end"))?;
+    /// Note: `day_end` is included in the query range. Entries running across the range are
+    /// clipped to it, see [`ClockingStore::finished_overlapping_dates`] for whole entries.
+    fn finished_by_date_str(
+        &self,
+        day_start: &str,
+        day_end: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<FinishedEntry<'_>>> {
+        let (start, end) = store_helper::parse_date_range(day_start, day_end)?;
+        let entries = self.finished(&start, Some(end), filter)?;
+        Ok(store_helper::clip(entries, &start, &end))
+    }
 
-        if end_date < start_date {
-            Err(errors::Error::InvalidInput(
-                "day_end must not before day_start",
-            ))
-        } else {
-            let today_naive = Local::now().date_naive();
-            let local_fixed_offset = Local.offset_from_local_date(&today_naive).unwrap();
-            let start_dt = DateTime::<FixedOffset>::from_local(
-                start_date.and_hms_opt(0, 0, 0).unwrap(),
-                local_fixed_offset,
-            )
-            .with_timezone(&Utc);
-            let end_dt = DateTime::<FixedOffset>::from_local(
-                end_date.and_hms_opt(23, 59, 59).unwrap(),
-                local_fixed_offset,
-            )
-            .with_timezone(&Utc);
-
-            self.finished(&start_dt, Some(end_dt))
-        }
+    /// Query whole finished clocking entries overlapping 'yyyy-mm-dd' local dates, e.g. for export.
+    ///
+    /// Note: `day_end` is included in the query range.
+    fn finished_overlapping_dates(
+        &self,
+        day_start: &str,
+        day_end: &str,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<FinishedEntry<'_>>> {
+        let (start, end) = store_helper::parse_date_range(day_start, day_end)?;
+        self.finished(&start, Some(end), filter)
     }
 
     /// Fetch latest-started finished clocking entry by title.
-    fn latest_finished(&self, title: &str) -> Result<Option<FinishedEntry>>;
+    fn latest_finished<'a>(&self, title: &str) -> Result<Option<FinishedEntry<'a>>>;
 
     /// Fetch at most `limit` latest-started finished clocking entries.
     fn recent_titles(&self, limit: usize) -> Result<Vec<String>>;
 
     /// Fetch at most `limit` latest-started unfinished clocking entries.
     fn unfinished<'a>(&self, limit: usize) -> Result<Vec<UnfinishedEntry<'a>>>;
+
+    /// Fetch finished entries overlapping time span `(start, end)`, ordered by start.
+    fn finished_overlapping<'a>(
+        &self,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<FinishedEntry<'a>>>;
+
+    /// Fetch finished entries running at `at`, i.e. `start <= at <= end`, ordered by start.
+    fn finished_covering<'a>(&self, at: &DateTime<Utc>) -> Result<Vec<FinishedEntry<'a>>>;
+
+    /// Update title, start, end and notes of the entry identified by `id` to those of `updated`.
+    ///
+    /// Updating an unfinished entry finishes it. `updated.end` must be after `updated.id.start`,
+    /// and no other entry may have the same title and start. Tags are not changed, pauses are
+    /// closed at or clipped to the new span, those outside of it are removed.
+    /// Returns false if the entry is not found.
+    fn update_entry(&mut self, id: &EntryId, updated: &FinishedEntry) -> Result<bool>;
+
+    /// Split the finished entry identified by `id` around `[gap_start, gap_end]` at once, into the
+    /// part before the gap and a new entry of the same title, notes and tags after it.
+    ///
+    /// Pauses are clipped to the parts. The gap must be within the entry leaving both parts
+    /// non-empty. Returns false if the entry is not found or unfinished.
+    fn split_entry(
+        &mut self,
+        id: &EntryId,
+        gap_start: &DateTime<Utc>,
+        gap_end: &DateTime<Utc>,
+    ) -> Result<bool>;
+
+    /// Delete the finished or unfinished entry identified by `id`.
+    ///
+    /// Returns false if the entry is not found.
+    fn delete_entry(&mut self, id: &EntryId) -> Result<bool>;
+
+    /// Add `tags` to a finished or unfinished entry, tags already on the entry are ignored.
+    ///
+    /// Returns false if the entry is not found.
+    fn add_tags(&mut self, id: &EntryId, tags: &[String]) -> Result<bool>;
+
+    /// Remove `tags` from a finished or unfinished entry.
+    ///
+    /// Returns false if the entry is not found.
+    fn remove_tags(&mut self, id: &EntryId, tags: &[String]) -> Result<bool>;
+
+    /// Fetch all tags attached to any entry, ordered by name.
+    fn tags(&self) -> Result<Vec<String>>;
+
+    /// Check all entries for overlaps, entries not ending after start, corrupt rows
+    /// and unfinished entries started more than `stale_after` ago.
+    fn integrity_report<'a>(&self, stale_after: chrono::Duration) -> Result<IntegrityReport<'a>>;
+
+    /// When on, queries skip corrupt entries with a logged warning instead of failing
+    /// with [`errors::Error::ImpossibleState`].
+    fn skip_corrupt(&mut self, skip: bool);
 }
 
 pub(crate) mod store_helper {
+    use crate::errors::Error;
+    use crate::types::{FinishedEntry, Pause};
     use chrono::naive::Days as NaiveDays;
     use chrono::prelude::*;
 
+    /// Find index pairs of overlapping entries, `entries` must be sorted by start.
+    ///
+    /// Entries not ending after start are ignored.
+    pub(crate) fn find_overlaps(entries: &[FinishedEntry]) -> Vec<(usize, usize)> {
+        let mut overlaps = vec![];
+        // indexes of entries still running at the current start
+        let mut running: Vec<usize> = vec![];
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.end <= entry.id.start {
+                continue;
+            }
+            running.retain(|&j| entries[j].end > entry.id.start);
+            overlaps.extend(running.iter().map(|&j| (j, i)));
+            running.push(i);
+        }
+        overlaps
+    }
+
+    /// Times are stored in RFC 3339, whose years have four digits.
+    pub(crate) fn validate_time(time: &DateTime<Utc>) -> Result<(), Error> {
+        if (0..=9999).contains(&time.year()) {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(
+                "time must be within years 0000 to 9999",
+            ))
+        }
+    }
+
+    /// Characters ending a word of the filter language, which can not be in `tag:name`.
+    pub(crate) fn is_tag_breaking(c: char) -> bool {
+        c.is_whitespace() || c.is_control() || matches!(c, '(' | ')' | '"')
+    }
+
+    /// Tag names are referenced by filter term `tag:name`, so they must be non-empty without
+    /// characters breaking it, see [`is_tag_breaking`].
+    pub(crate) fn validate_tags(tags: &[String]) -> Result<(), Error> {
+        if tags
+            .iter()
+            .all(|t| !t.is_empty() && !t.contains(is_tag_breaking))
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(
+                "tag must be non-empty without whitespace, parentheses or quotes",
+            ))
+        }
+    }
+
+    /// Pauses of an entry finished in `[start, end]` must be finished within it, without
+    /// overlapping each other.
+    pub(crate) fn validate_pauses(
+        pauses: &[Pause],
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let mut spans = vec![];
+        for pause in pauses {
+            match pause.end {
+                None => return Err(Error::InvalidInput("pause of finished entry must end")),
+                Some(pause_end) if pause_end < pause.start => {
+                    return Err(Error::InvalidInput("pause must not end before start"))
+                }
+                Some(pause_end) if pause.start < *start || pause_end > *end => {
+                    return Err(Error::InvalidInput("pause must be within the entry"))
+                }
+                Some(pause_end) => spans.push((pause.start, pause_end)),
+            }
+        }
+        spans.sort();
+        if spans.windows(2).any(|w| w[1].0 < w[0].1) {
+            return Err(Error::InvalidInput("pauses must not overlap"));
+        }
+        Ok(())
+    }
+
     pub(crate) fn query_start_end(
         days_offset: u64,
         days: Option<u64>,
     ) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
-        let today_naive = Local::now().date_naive();
-        let local_fixed_offset = Local.offset_from_local_date(&today_naive).unwrap();
-        let today_naive = today_naive.and_hms_opt(0, 0, 0).unwrap();
-
-        let start_naive = today_naive
-            .checked_sub_days(NaiveDays::new(days_offset))
-            .unwrap();
-        let start_in_utc = DateTime::<FixedOffset>::from_local(start_naive, local_fixed_offset)
-            .with_timezone(&Utc);
-        let end_in_utc = days.map(|d| {
-            let end_naive = start_naive.checked_add_days(NaiveDays::new(d)).unwrap();
-            DateTime::<FixedOffset>::from_local(end_naive, local_fixed_offset).with_timezone(&Utc)
-        });
-
-        (start_in_utc, end_in_utc)
+        query_start_end_in(Local::now().date_naive(), days_offset, days, &Local)
+    }
+
+    /// Range of `days` local dates from `days_offset` days before `today` in `tz`.
+    pub(crate) fn query_start_end_in<Tz: TimeZone>(
+        today: NaiveDate,
+        days_offset: u64,
+        days: Option<u64>,
+        tz: &Tz,
+    ) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
+        let start_date = today.checked_sub_days(NaiveDays::new(days_offset)).unwrap();
+        let end_date = days.map(|d| start_date.checked_add_days(NaiveDays::new(d)).unwrap());
+        (
+            day_start(start_date, tz),
+            end_date.map(|date| day_start(date, tz)),
+        )
+    }
+
+    /// Range from the start of local date `start_date` to the end of `end_date` in `tz`, which
+    /// is the start of the next date.
+    pub(crate) fn date_range<Tz: TimeZone>(
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        tz: &Tz,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        (
+            day_start(start_date, tz),
+            day_start(end_date.succ_opt().unwrap(), tz),
+        )
+    }
+
+    /// Parse 'yyyy-mm-dd' local dates into the range of [`date_range`].
+    pub(crate) fn parse_date_range(
+        day_start: &str,
+        day_end: &str,
+    ) -> Result<(DateTime<Utc>, DateTime<Utc>), Error> {
+        let start_date = NaiveDate::parse_from_str(day_start, crate::NAIVE_DATE_FORMAT)
+            .map_err(|_| Error::InvalidInput("day_start"))?;
+        let end_date = NaiveDate::parse_from_str(day_end, crate::NAIVE_DATE_FORMAT)
+            .map_err(|_| Error::InvalidInput("day_end"))?;
+        if end_date < start_date {
+            return Err(Error::InvalidInput("day_end must not before day_start"));
+        }
+        Ok(date_range(start_date, end_date, &Local))
+    }
+
+    /// Clip `entries` and their pauses to `[start, end)`, dropping those out of it.
+    pub(crate) fn clip<'a>(
+        entries: Vec<FinishedEntry<'a>>,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Vec<FinishedEntry<'a>> {
+        entries
+            .into_iter()
+            .filter_map(|mut entry| {
+                entry.id.start = entry.id.start.max(*start);
+                entry.end = entry.end.min(*end);
+                if entry.end <= entry.id.start {
+                    return None;
+                }
+                let (start, end) = (entry.id.start, entry.end);
+                entry
+                    .pauses
+                    .retain(|p| p.start < end && p.end.is_none_or(|e| e > start));
+                for pause in entry.pauses.iter_mut() {
+                    pause.start = pause.start.max(start);
+                    pause.end = pause.end.map(|e| e.min(end));
+                }
+                Some(entry)
+            })
+            .collect()
+    }
+
+    /// Start of local `date` in `tz`, offsets are of the date instead of today's.
+    pub(crate) fn day_start<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+        local_to_utc(&date.and_hms_opt(0, 0, 0).unwrap(), tz)
+    }
+
+    /// Resolve a naive local date time in `tz`, the earlier one is taken if it's ambiguous,
+    /// and the first existing one after it if it's skipped by a DST change.
+    pub(crate) fn local_to_utc<Tz: TimeZone>(naive: &NaiveDateTime, tz: &Tz) -> DateTime<Utc> {
+        // DST gaps are no longer than a few hours
+        (0..=24 * 4)
+            .map(|n| *naive + chrono::Duration::minutes(15 * n))
+            .find_map(|t| tz.from_local_datetime(&t).earliest())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(naive))
     }
 }
 
+/// Central European Time of 2023 for tests, which observes DST from 03-26 to 10-29.
+#[cfg(test)]
+pub(crate) mod test_tz {
+    use chrono::prelude::*;
+    use chrono::LocalResult;
+
+    #[derive(Clone, Copy, Debug)]
+    pub(crate) struct Cet;
+
+    fn is_dst(utc: &NaiveDateTime) -> bool {
+        let at = |m, d| {
+            NaiveDate::from_ymd_opt(2023, m, d)
+                .unwrap()
+                .and_hms_opt(1, 0, 0)
+                .unwrap()
+        };
+        at(3, 26) <= *utc && *utc < at(10, 29)
+    }
+
+    fn offset(dst: bool) -> FixedOffset {
+        FixedOffset::east_opt(if dst { 2 * 3600 } else { 3600 }).unwrap()
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let valid: Vec<FixedOffset> = [true, false]
+                .into_iter()
+                .filter(|dst| {
+                    let utc =
+                        *local - chrono::Duration::seconds(offset(*dst).local_minus_utc().into());
+                    is_dst(&utc) == *dst
+                })
+                .map(offset)
+                .collect();
+            match valid[..] {
+                [only] => LocalResult::Single(only),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            offset(is_dst(utc))
+        }
+    }
+}
+
+/// Format an amount in cents like `1250.00`.
+pub(crate) fn strify_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{sign}{}.{:0>2}", cents.abs() / 100, cents.abs() % 100)
+}
+
 const HOUR_MINUTES: i64 = 60;
 const DAY_MINUTES: i64 = HOUR_MINUTES * 24;
 pub(crate) fn strify_duration(d: &chrono::Duration) -> String {
@@ -152,3 +607,37 @@ pub(crate) fn strify_duration(d: &chrono::Duration) -> String {
         format!("{}:{:0>2}:{:0>2}", days, hours, minutes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::store_helper::*;
+    use super::test_tz::Cet;
+    use chrono::prelude::*;
+
+    #[test]
+    fn ranges_across_dst() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+        let utc = |m, d, h| Utc.with_ymd_and_hms(2023, m, d, h, 0, 0).unwrap();
+
+        // the offset of each date is used, not the one of today
+        assert_eq!(
+            query_start_end_in(date(3, 27), 2, Some(2), &Cet),
+            (utc(3, 24, 23), Some(utc(3, 26, 22)))
+        );
+        assert_eq!(
+            query_start_end_in(date(10, 30), 2, None, &Cet),
+            (utc(10, 27, 22), None)
+        );
+        let (start, end) = date_range(date(10, 29), date(10, 29), &Cet);
+        assert_eq!(start, utc(10, 28, 22));
+        assert_eq!(end - start, chrono::Duration::hours(25));
+
+        let skipped = date(3, 26).and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(local_to_utc(&skipped, &Cet), utc(3, 26, 1));
+        let ambiguous = date(10, 29).and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(
+            local_to_utc(&ambiguous, &Cet),
+            utc(10, 29, 0) + chrono::Duration::minutes(30)
+        );
+    }
+}
@@ -1,7 +1,17 @@
+use chrono::prelude::*;
 use clap::{Parser, Subcommand};
-use clocking::{errors, new_sqlite_store, ClockingStore};
+use clocking::config::{Config, ReportView};
+use clocking::timeparse::format_duration;
+use clocking::types::{
+    EntryId, FinishedEntry, Goal, GoalKind, GoalPeriod, GoalSubject, Rate, RoundScope, Rounding,
+    TrackingMode, UnfinishedEntry, Workday, WEEKDAYS,
+};
+use clocking::{errors, exchange, new_sqlite_store, ClockingStore};
+use serde::Serialize;
+use serde_json::json;
 use std::env;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 #[derive(Parser, Debug)]
@@ -10,9 +20,84 @@ use std::sync::Mutex;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
-    /// File path to store the data, required if no environment variable 'CLOCKING_FILE' detected.  Take priority of environment variable 'CLOCKING_FILE'.
+    /// File path to store the data, required if neither environment variable 'CLOCKING_FILE' nor 'file' of the config file is set.  Take priority of both.
     #[arg(long)]
     file: Option<String>,
+    /// Output format, json and ndjson (a json value per line) are for scripts.
+    #[arg(long, value_enum, default_value_t = Output::Text, global = true)]
+    output: Output,
+}
+
+/// File format of export.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    /// Columns: title,start,end,notes,tags,pauses
+    Csv,
+    /// iCalendar events
+    Ics,
+}
+
+/// File format of import.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ImportFormat {
+    /// Written by export
+    Csv,
+    /// Data files of timewarrior, e.g. ~/.timewarrior/data/2023-01.data
+    Timewarrior,
+    /// Detailed report csv of Toggl Track
+    Toggl,
+    /// CLOCK lines of org-mode files
+    Org,
+}
+
+impl ImportFormat {
+    fn importer(self, tags_into: exchange::TagsInto) -> Box<dyn exchange::Importer> {
+        match self {
+            ImportFormat::Csv => Box::new(exchange::Csv),
+            ImportFormat::Timewarrior => Box::new(exchange::Timewarrior { tags_into }),
+            ImportFormat::Toggl => Box::new(exchange::Toggl { tags_into }),
+            ImportFormat::Org => Box::new(exchange::Org { tags_into }),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum TagsInto {
+    Title,
+    Tags,
+}
+
+impl From<TagsInto> for exchange::TagsInto {
+    fn from(t: TagsInto) -> Self {
+        match t {
+            TagsInto::Title => exchange::TagsInto::Title,
+            TagsInto::Tags => exchange::TagsInto::Tags,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Output {
+    Text,
+    Json,
+    Ndjson,
+}
+
+impl Output {
+    /// Print `value` by `text` for text output, otherwise serialize it.
+    ///
+    /// Elements of a sequence are printed one per line for ndjson.
+    fn print<T: Serialize + ?Sized>(self, value: &T, text: impl FnOnce()) {
+        let json = || serde_json::to_value(value).expect("values are serializable to json");
+        match self {
+            Output::Text => text(),
+            Output::Json => println!("{:#}", json()),
+            Output::Ndjson => match json() {
+                serde_json::Value::Array(values) => values.iter().for_each(|v| println!("{v}")),
+                value => println!("{value}"),
+            },
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -27,13 +112,77 @@ enum Commands {
         /// Do not wait for notes input, exit with unfinished status.
         #[arg(short, long)]
         no_wait: bool,
+        /// Tag the entry, can be specified multiple times.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Start at a past time: 'HH:MM', 'yesterday HH:MM', 'YYYY-MM-DD HH:MM' or RFC 3339.
+        /// Must not be before the end of the latest finished entry.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true, conflicts_with = "ago")]
+        at: Option<DateTime<Utc>>,
+        /// Start this long ago, like '20m' or '1h30m'.
+        #[arg(long, value_parser = clocking::timeparse::parse_duration)]
+        ago: Option<chrono::Duration>,
+    },
+    /// Log an entry finished already.
+    Log {
+        title: String,
+        /// Start time: 'HH:MM', 'yesterday HH:MM', 'YYYY-MM-DD HH:MM', '20m ago', '-1h30m' or RFC 3339.
+        #[arg(short, long, value_parser = parse_time, allow_hyphen_values = true)]
+        start: DateTime<Utc>,
+        /// End time in the same formats as '--start'. Default to now.
+        #[arg(short, long, value_parser = parse_time, allow_hyphen_values = true, conflicts_with = "duration")]
+        end: Option<DateTime<Utc>>,
+        /// Duration from start instead of end time, like '45m' or '1h30m'.
+        #[arg(short, long, value_parser = clocking::timeparse::parse_duration)]
+        duration: Option<chrono::Duration>,
+        /// Can be specified multiple times, each as a separate line. Sinel value '-' means read from stdin.
+        #[arg(short, long)]
+        notes: Vec<String>,
+        /// Tag the entry, can be specified multiple times.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Log even if it overlaps existing entries.
+        #[arg(long)]
+        force: bool,
     },
     /// Finish latest unfinished clocking of title.
     Finish {
+        /// Title of the entry to finish. Choose interactively if several unfinished entries match.
+        title: Option<String>,
+        /// End time: 'HH:MM', 'yesterday HH:MM', 'YYYY-MM-DD HH:MM', '20m ago', '-1h30m' or RFC 3339. Default to now.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        at: Option<DateTime<Utc>>,
         /// Can be specified multiple times, each as a separate line. Sinel value '-' means read from stdin.
         #[arg(short, long)]
         notes: Vec<String>,
     },
+    /// Finish the unfinished entry and start another one at the same time.
+    Switch {
+        /// Title of the new entry. If not specified, interactively choose from recent titles.
+        title: Option<String>,
+        /// Notes of the finished entry, can be specified multiple times, each as a separate line. Sinel value '-' means read from stdin.
+        #[arg(short, long)]
+        notes: Vec<String>,
+        /// Tag the new entry, can be specified multiple times.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Pause the unfinished entry, e.g. for a break.
+    Pause {
+        /// Title of the entry to pause. Choose interactively if several running entries match.
+        title: Option<String>,
+        /// Pause time: 'HH:MM', 'YYYY-MM-DD HH:MM', '20m ago', '-1h30m' or RFC 3339. Default to now.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        at: Option<DateTime<Utc>>,
+    },
+    /// Resume the paused unfinished entry.
+    Resume {
+        /// Title of the entry to resume. Choose interactively if several paused entries match.
+        title: Option<String>,
+        /// Resume time in the same formats as 'pause --at'. Default to now.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        at: Option<DateTime<Utc>>,
+    },
     /// Report clocking data.
     Report {
         ///Tail offset. Default to 0 - today
@@ -43,7 +192,7 @@ enum Commands {
         #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
         days: Option<u64>,
         ///Show daily summary
-        #[arg(long = "daily")]
+        #[arg(long = "daily", conflicts_with_all = ["weekly", "monthly"])]
         daily_summary: bool,
         ///Show detail report
         #[arg(long)]
@@ -51,9 +200,54 @@ enum Commands {
         /// Show daily distribution
         #[arg(long = "dist")]
         daily_dist: bool,
-        ///<Unimplemented yet>.
+        /// Show summary by tags
+        #[arg(long = "by-tag")]
+        tag_summary: bool,
+        /// Show billed time and amounts by clients, see 'rate' for setting rates
+        #[arg(long)]
+        billing: bool,
+        /// Show totals by projects of titles, nested by '/' like 'clocking/server'
+        #[arg(long)]
+        tree: bool,
+        /// Collapse levels of '--tree' deeper than this, implies '--tree'
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        depth: Option<u64>,
+        /// Show weekly summary, or weekly detail along with '--detail'
+        #[arg(long, conflicts_with = "monthly")]
+        weekly: bool,
+        /// Show monthly summary, or monthly detail along with '--detail'
+        #[arg(long)]
+        monthly: bool,
+        /// First day of week for '--weekly'. Default to 'week_start' of the config file, or Monday.
+        #[arg(long)]
+        week_start: Option<Weekday>,
+        /// Only report entries having the tag, can be specified multiple times.
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Skip corrupt entries with a warning instead of failing the report.
+        #[arg(long)]
+        skip_corrupt: bool,
+        /// Split time shared by overlapping entries evenly among them instead of counting it for each.
+        #[arg(long)]
+        split_overlaps: bool,
+        /// Override the start of working hours of every working day for '--dist', like '09:00'.
+        #[arg(long, value_parser = parse_clock_time)]
+        day_start: Option<NaiveTime>,
+        /// Override the end of working hours of every working day for '--dist', like '18:00'.
+        #[arg(long, value_parser = parse_clock_time)]
+        day_end: Option<NaiveTime>,
+        /// Round durations to multiples of minutes, like '15m-up', '6m-nearest' or '30m'.
+        #[arg(long)]
+        round: Option<Rounding>,
+        /// Round the time of each 'entry' on a date, or the 'day' total of each title.
+        #[arg(long, requires = "round", default_value_t = RoundScope::Entry)]
+        round_per: RoundScope,
+        /// Only report entries whose title matches the filter expression.
+        ///
+        /// Terms: `word` or `"some words"` (title contains), `glob*` (`*`, `?`, `[...]` on whole title),
+        /// `/regex/`. Combine terms by `and`, `or`, `not` and parentheses.
         #[arg(long)]
-        filter: Option<String>,
+        filter: Option<clocking::filter::Filter>,
     },
     /// Show details of latest record of item 'title'.
     Latest {
@@ -62,6 +256,159 @@ enum Commands {
     },
     /// Shoe latest unfinished entry
     Ongoing,
+    /// Add or remove tags of the latest entry of title.
+    Tag {
+        /// Title of the entry, default to the latest unfinished entry.
+        title: Option<String>,
+        /// Tag to add, can be specified multiple times.
+        #[arg(short, long)]
+        add: Vec<String>,
+        /// Tag to remove, can be specified multiple times.
+        #[arg(short, long)]
+        remove: Vec<String>,
+    },
+    /// Show all tags
+    Tags,
+    /// Edit a finished entry, the latest one of title by default.
+    Edit {
+        /// Title of the entry. Choose interactively if neither title nor '--at' is specified.
+        title: Option<String>,
+        /// Select the entry running at this time: 'HH:MM', 'YYYY-MM-DD HH:MM' or RFC 3339.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        at: Option<DateTime<Utc>>,
+        /// New title
+        #[arg(long)]
+        rename: Option<String>,
+        /// New start time
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        start: Option<DateTime<Utc>>,
+        /// New end time
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        end: Option<DateTime<Utc>>,
+        /// Replace notes, can be specified multiple times, each as a separate line. Sinel value '-' means read from stdin.
+        #[arg(short, long)]
+        notes: Vec<String>,
+    },
+    /// Delete a finished entry, the latest one of title by default.
+    Delete {
+        /// Title of the entry. Choose interactively if neither title nor '--at' is specified.
+        title: Option<String>,
+        /// Select the entry running at this time: 'HH:MM', 'YYYY-MM-DD HH:MM' or RFC 3339.
+        #[arg(long, value_parser = parse_time, allow_hyphen_values = true)]
+        at: Option<DateTime<Utc>>,
+        /// Delete without confirmation.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Check entries for overlaps, non-positive durations, corrupt timestamps and stale unfinished entries.
+    Check {
+        /// Interactively trim or split overlapping entries and delete non-positive ones.
+        #[arg(long)]
+        fix: bool,
+        /// Report unfinished entries started more than this many hours ago.
+        #[arg(long, default_value_t = 24)]
+        stale_hours: u32,
+    },
+    /// Show or change whether multiple unfinished entries may run at the same time.
+    Mode {
+        /// 'single' or 'parallel', show the current mode if not specified.
+        mode: Option<TrackingMode>,
+    },
+    /// Show or change the maximum entry length, unfinished entries running longer are prompted to finish.
+    MaxLength {
+        /// Duration like '12h' or '1d', or 'off' to disable. Show the current one if not specified.
+        length: Option<String>,
+    },
+    /// Export finished entries started within the date range.
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// First local date 'YYYY-MM-DD' to export, default to the earliest.
+        #[arg(long)]
+        from: Option<String>,
+        /// Last local date 'YYYY-MM-DD' to export, default to today.
+        #[arg(long)]
+        to: Option<String>,
+        /// Write to the file instead of stdout.
+        #[arg(long, short = 'o')]
+        out: Option<PathBuf>,
+    },
+    /// Import finished entries, skipping ones with the same title and start as existing entries.
+    Import {
+        /// Files to import, '-' means stdin.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ImportFormat::Csv)]
+        format: ImportFormat,
+        /// Where tags of timewarrior, toggl and org go, 'title' appends them to the title.
+        #[arg(long, value_enum, default_value_t = TagsInto::Title)]
+        tags_into: TagsInto,
+        /// Report what would be imported and the duplicates without saving anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show or change working hours, idle time within them is shown by 'report --dist'.
+    Workday {
+        /// Hours like '09:00-17:00', or 'off'. Show working hours of each weekday if not specified,
+        /// including the ones overridden by '[workday]' of the config file.
+        hours: Option<String>,
+        /// Weekday to change, can be specified multiple times. Default to every day.
+        #[arg(short, long)]
+        weekday: Vec<Weekday>,
+    },
+    /// Show, set or remove hourly rates of projects billed to clients.
+    Rate {
+        /// Title or project like 'acme' for 'acme/website'. Show all rates if not specified,
+        /// or the rate applying to it if neither '--client' nor '--hourly' is.
+        project: Option<String>,
+        /// Client billed for the project.
+        #[arg(long, requires_all = ["project", "hourly"])]
+        client: Option<String>,
+        /// Amount per hour like '120' or '99.50'.
+        #[arg(long, value_parser = parse_cents, requires_all = ["project", "client"])]
+        hourly: Option<i64>,
+        /// Remove the rate of the project.
+        #[arg(long, requires = "project", conflicts_with_all = ["client", "hourly"])]
+        remove: bool,
+    },
+    /// Print an invoice body of a client in markdown, see 'rate' for setting rates.
+    Invoice {
+        #[arg(long)]
+        client: String,
+        /// Month 'YYYY-MM' to bill, default to the previous month.
+        #[arg(long, value_parser = parse_month)]
+        month: Option<NaiveDate>,
+        /// Render the invoice as html.
+        #[arg(long)]
+        html: bool,
+        /// Round billed time, like '15m-up', see 'report --round'.
+        #[arg(long)]
+        round: Option<Rounding>,
+        /// Round the time of each 'entry' on a date, or the 'day' total of each title.
+        #[arg(long, requires = "round", default_value_t = RoundScope::Entry)]
+        round_per: RoundScope,
+    },
+    /// Show progress of goals, or set or remove a goal of a title or tag.
+    Goal {
+        /// Title like 'reading', covering its sub projects, or 'tag:meeting'.
+        /// Show progress of all goals if not specified.
+        subject: Option<GoalSubject>,
+        /// Time to spend at least, like '8h'.
+        #[arg(long, value_parser = clocking::timeparse::parse_duration, requires = "subject")]
+        target: Option<chrono::Duration>,
+        /// Time to spend at most, like '10h'. Starting an entry over the cap prints a warning.
+        #[arg(long, value_parser = clocking::timeparse::parse_duration, requires = "subject", conflicts_with = "target")]
+        cap: Option<chrono::Duration>,
+        /// Remove the 'target' or 'cap' goal of the subject.
+        #[arg(long, requires = "subject", conflicts_with_all = ["target", "cap"])]
+        remove: Option<GoalKind>,
+        /// Period of the goal.
+        #[arg(long, default_value_t = GoalPeriod::Week)]
+        per: GoalPeriod,
+        /// First day of week for weekly goals. Default to 'week_start' of the config file, or Monday.
+        #[arg(long)]
+        week_start: Option<Weekday>,
+    },
     /// Show latest n titles
     Titles {
         /// Number of titles to show
@@ -73,39 +420,118 @@ enum Commands {
     },
     /// Server mode
     Server {
-        /// Default to 8080
+        /// Default to 'port' of the config file, or 8080
         #[arg(long, short)]
         port: Option<u16>,
-        /// Default to 127.0.0.1
+        /// Default to 'addr' of the config file, or 127.0.0.1
         #[arg(long, short)]
         addr: Option<std::net::IpAddr>,
+        /// Skip corrupt entries with a warning instead of failing the requests.
+        #[arg(long)]
+        skip_corrupt: bool,
+    },
+    /// Persistent configuration, see 'clocking config show' for its path and keys.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Show the effective configuration, after command line and environment overrides.
+    Show,
+}
+
 const STORE_FILE_VAR: &str = "CLOCKING_FILE";
-const RECENT_TITLE_LIMIT: usize = 5;
+
 #[rocket::main]
 async fn main() -> Result<(), errors::Error> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
     let cli = Cli::parse();
 
-    let store_file = cli
+    let config_path = Config::path();
+    let mut config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    config.file = cli
         .file
         .or_else(|| env::var(STORE_FILE_VAR).ok())
-        .expect("Please specify storage file path either by environment or cli argument --file before any command.");
+        .or(config.file);
+
+    let output = cli.output;
+    if let Commands::Config {
+        command: ConfigCommand::Show,
+    } = cli.command
+    {
+        output.print(&json!({"path": config_path, "config": config}), || {
+            if let Some(path) = &config_path {
+                println!("# {}", path.display());
+            }
+            print!("{}", config.to_toml());
+        });
+        return Ok(());
+    }
+    let store_file = config.file.clone().expect(
+        "Please specify storage file path by cli argument --file, environment or the config file before any command.",
+    );
+    let recent_limit = config.limits.recent_titles;
+
+    if !matches!(
+        cli.command,
+        Commands::Server { .. } | Commands::MaxLength { .. }
+    ) {
+        // prompts would be mixed into json output, or be hidden if stdout is redirected
+        let interactive =
+            output == Output::Text && io::stdin().is_terminal() && io::stdout().is_terminal();
+        prompt_overdue(&mut new_sqlite_store(&store_file)?, interactive)?;
+    }
 
     match cli.command {
-        Commands::Start { title, no_wait } => {
-            let mut store = new_sqlite_store(&store_file);
-            let title = handle_title(title, &store.recent_titles(RECENT_TITLE_LIMIT)?);
+        Commands::Start {
+            title,
+            no_wait,
+            tags,
+            at,
+            ago,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let title = handle_title(title, &store.recent_titles(recent_limit)?);
+            let start = match (at, ago) {
+                (Some(at), _) => at,
+                (None, Some(ago)) => Utc::now()
+                    .checked_sub_signed(ago)
+                    .ok_or(errors::Error::InvalidInput("start is too long ago"))?,
+                (None, None) => Utc::now(),
+            };
             match title {
                 Ok(title) => {
-                    let _ = store.start(&title)?;
-                    println!("(Started)");
+                    let entry = UnfinishedEntry {
+                        id: EntryId {
+                            title: title.as_str().into(),
+                            start,
+                        },
+                        notes: "".into(),
+                        tags,
+                        pauses: vec![],
+                    };
+                    store.start_entry_since(&entry)?;
+                    // under json, only the finished entry is printed as a single document
+                    if no_wait || output != Output::Json {
+                        output.print(&json!({"status": "started", "entry": entry}), || {
+                            println!("(Started)")
+                        });
+                    }
+                    warn_exceeded_caps(&store, &entry.id.title, &entry.tags, &config);
                     if !no_wait {
-                        println!("(Ctrl-D to finish clocking)");
+                        eprintln!("(Ctrl-D to finish clocking)");
                         let notes = read_to_end();
-                        if store.try_finish_any(&notes).is_ok() {
-                            println!("(Finished)");
+                        // the latest started entry may be another one in parallel mode
+                        if store.try_finish_entry(&entry.id, &Utc::now(), &notes)? {
+                            output.print(&json!({"status": "finished", "id": entry.id}), || {
+                                println!("(Finished)")
+                            });
                         } else {
                             return Err(errors::Error::ImpossibleState(
                                     "We should be able to finish it, but somehow it's already finished...".to_string()));
@@ -117,17 +543,123 @@ async fn main() -> Result<(), errors::Error> {
                 }
             };
         }
-        Commands::Finish { notes } => {
-            let mut store = new_sqlite_store(&store_file);
-            let notes = if notes.len() == 1 && notes[0] == "-" {
-                read_to_end()
-            } else {
-                notes.join("\n")
+        Commands::Log {
+            title,
+            start,
+            end,
+            duration,
+            notes,
+            tags,
+            force,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let end = match duration {
+                Some(duration) => start
+                    .checked_add_signed(duration)
+                    .ok_or(errors::Error::InvalidInput("end is out of range"))?,
+                None => end.unwrap_or_else(Utc::now),
+            };
+            let entry = FinishedEntry {
+                id: EntryId {
+                    title: title.into(),
+                    start,
+                },
+                end,
+                notes: read_notes(notes).into(),
+                tags,
+                pauses: vec![],
             };
-            match store.try_finish_any(&notes) {
-                Ok(Some(title)) => println!("(Finished: {title})"),
-                Ok(None) => println!("(No unfinished item found)"),
-                Err(e) => eprintln!("Unexpected error: {e}"),
+
+            let overlapping = store.finished_overlapping(&entry.id.start, &entry.end)?;
+            if !force && !overlapping.is_empty() {
+                eprintln!("(Overlaps with existing entries, use '--force' to log anyway)");
+                for other in overlapping.iter() {
+                    eprint!("{other}");
+                }
+            } else {
+                store.add_finished_entry(&entry)?;
+                output.print(&json!({"status": "logged", "entry": entry}), || {
+                    print!("(Logged)\n{entry}")
+                });
+            }
+        }
+        Commands::Finish { title, at, notes } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let end = at.unwrap_or_else(Utc::now);
+            match select_unfinished(&store, title.as_deref(), |_| true)? {
+                Some(entry) if end <= entry.id.start => {
+                    return Err(errors::Error::InvalidInput(
+                        "end must be after start of the entry",
+                    ))
+                }
+                Some(entry) => {
+                    if store.try_finish_entry(&entry.id, &end, &read_notes(notes))? {
+                        output.print(&json!({"status": "finished", "id": entry.id}), || {
+                            println!("(Finished: {})", entry.id.title)
+                        });
+                    } else {
+                        output.print(
+                            &json!({"status": "finished_already", "id": entry.id}),
+                            || println!("(Finished already: {})", entry.id.title),
+                        );
+                    }
+                }
+                None => output.print(&json!({"status": "not_found"}), || {
+                    println!("(No unfinished item found)")
+                }),
+            }
+        }
+        Commands::Switch { title, notes, tags } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match handle_title(title, &store.recent_titles(recent_limit)?) {
+                Ok(title) => {
+                    let entry = UnfinishedEntry {
+                        id: EntryId {
+                            title: title.as_str().into(),
+                            start: Utc::now(),
+                        },
+                        notes: "".into(),
+                        tags,
+                        pauses: vec![],
+                    };
+                    let notes = read_notes(notes);
+                    let finished = store.switch_to_entry(&entry, &notes)?;
+                    let value = json!({"status": "switched", "finished": finished, "entry": entry});
+                    output.print(&value, || {
+                        match finished {
+                            Some(finished) => println!("(Finished: {finished})"),
+                            None => println!("(No unfinished item found)"),
+                        }
+                        println!("(Started: {title})");
+                    });
+                }
+                Err(err) => eprintln!("Error reading or choosing title: {err}."),
+            }
+        }
+        Commands::Pause { title, at } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let at = at.unwrap_or_else(Utc::now);
+            match select_unfinished(&store, title.as_deref(), |e| !e.is_paused())? {
+                Some(entry) if store.try_pause_entry(&entry.id, &at)? => output
+                    .print(&json!({"status": "paused", "id": entry.id}), || {
+                        println!("(Paused: {})", entry.id.title)
+                    }),
+                _ => output.print(&json!({"status": "not_found"}), || {
+                    println!("(No running item found)")
+                }),
+            }
+        }
+        Commands::Resume { title, at } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let at = at.unwrap_or_else(Utc::now);
+            match select_unfinished(&store, title.as_deref(), |e| e.is_paused())? {
+                Some(entry) if store.try_resume_entry(&entry.id, &at)? => output
+                    .print(&json!({"status": "resumed", "id": entry.id}), || {
+                        println!("(Resumed: {})", entry.id.title)
+                    }),
+                _ => output.print(&json!({"status": "not_found"}), || {
+                    println!("(No paused item found)")
+                }),
             }
         }
         Commands::Report {
@@ -136,64 +668,824 @@ async fn main() -> Result<(), errors::Error> {
             daily_summary,
             detail,
             daily_dist,
-            ..
+            tag_summary,
+            billing,
+            tree,
+            depth,
+            weekly,
+            monthly,
+            week_start,
+            filter,
+            tags,
+            skip_corrupt,
+            split_overlaps,
+            day_start,
+            day_end,
+            round,
+            round_per,
         } => {
-            let store = new_sqlite_store(&store_file);
-            let entries = store.finished_by_offset(from.unwrap_or(0), days)?;
+            let rounding = round.map(|rounding| Rounding {
+                scope: round_per,
+                ..rounding
+            });
+            let mut store = new_sqlite_store(&store_file)?;
+            store.skip_corrupt(skip_corrupt);
+            let filter = clocking::filter::Filter::with_tags(filter, &tags);
+            let mut entries = store.finished_by_offset(from.unwrap_or(0), days, filter.as_ref())?;
+            if split_overlaps {
+                entries = clocking::views::split_overlaps(&entries);
+            }
 
-            if daily_summary {
-                let view = clocking::views::DailySummaryView::new(&entries);
-                println!("{view}");
+            let view = if daily_summary {
+                ReportView::Daily
+            } else if weekly && detail {
+                ReportView::WeeklyDetail
+            } else if weekly {
+                ReportView::Weekly
+            } else if monthly && detail {
+                ReportView::MonthlyDetail
+            } else if monthly {
+                ReportView::Monthly
             } else if detail {
-                let view = clocking::views::EntryDetailView::new(&entries);
-                println!("{view}");
+                ReportView::Detail
             } else if daily_dist {
-                let view = clocking::views::DailyDistributionView::new(&entries);
-                println!("{view}");
+                ReportView::Dist
+            } else if tag_summary {
+                ReportView::Tag
+            } else if billing {
+                ReportView::Billing
+            } else if tree || depth.is_some() {
+                ReportView::Tree
             } else {
-                let view = clocking::views::DailyDetailView::new(&entries);
-                println!("{view}");
+                config.report.view
+            };
+            let week_start = week_start.unwrap_or(config.report.week_start);
+            match view {
+                ReportView::Daily => {
+                    let view = clocking::views::DailySummaryView::new(&entries, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::WeeklyDetail => {
+                    let view =
+                        clocking::views::WeeklyDetailView::new(&entries, week_start, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Weekly => {
+                    let view =
+                        clocking::views::WeeklySummaryView::new(&entries, week_start, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::MonthlyDetail => {
+                    let view = clocking::views::MonthlyDetailView::new(&entries, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Monthly => {
+                    let view = clocking::views::MonthlySummaryView::new(&entries, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Detail => {
+                    let view = clocking::views::EntryDetailView::new(&entries, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Dist => {
+                    let workday = config.workday.apply(store.workday()?)?;
+                    let workday = workday.with_bounds(day_start, day_end)?;
+                    let view = clocking::views::DailyDistributionView::new(&entries, &workday);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Tag => {
+                    let view = clocking::views::TagSummaryView::new(&entries);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Billing => {
+                    let view =
+                        clocking::views::BillingView::new(&entries, &store.rates()?, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::Tree => {
+                    let depth = depth.map(|d| d as usize);
+                    let view = clocking::views::ProjectTreeView::new(&entries, depth);
+                    output.print(&view, || println!("{view}"));
+                }
+                ReportView::DailyDetail => {
+                    let view = clocking::views::DailyDetailView::new(&entries, rounding);
+                    output.print(&view, || println!("{view}"));
+                }
             }
         }
         Commands::Latest { title } => {
-            let store = new_sqlite_store(&store_file);
+            let store = new_sqlite_store(&store_file)?;
 
-            let title = handle_title(title, &store.recent_titles(RECENT_TITLE_LIMIT)?);
+            let title = handle_title(title, &store.recent_titles(recent_limit)?);
             match title {
-                Ok(title) => match store.latest_finished(&title)? {
-                    Some(item) => println!("{item}"),
-                    None => println!("(Not found)"),
-                },
+                Ok(title) => {
+                    let latest = store.latest_finished(&title)?;
+                    output.print(&latest, || match &latest {
+                        Some(item) => println!("{item}"),
+                        None => println!("(Not found)"),
+                    })
+                }
                 Err(err) => eprintln!("Error reading or choosing title: {err}."),
             }
         }
-        Commands::Ongoing => match new_sqlite_store(&store_file).unfinished(1)?.pop() {
-            Some(entry) => {
-                println!("{}", &entry.id.title);
-                println!("{} minutes ago", entry.started_minutes());
+        Commands::Ongoing => {
+            let store = new_sqlite_store(&store_file)?;
+            let ongoing = store.unfinished(1)?.pop();
+            if let Some(entry) = &ongoing {
+                warn_exceeded_caps(&store, &entry.id.title, &entry.tags, &config);
             }
-            None => println!("No ongoing entry."),
-        },
+            output.print(&ongoing, || match &ongoing {
+                Some(entry) => {
+                    println!("{}", &entry.id.title);
+                    println!("{} minutes ago", entry.started_minutes());
+                    if entry.is_paused() {
+                        println!("(Paused)");
+                    }
+                }
+                None => println!("No ongoing entry."),
+            })
+        }
+        Commands::Tag { title, add, remove } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let id = match title {
+                Some(title) => match store
+                    .unfinished(usize::MAX)?
+                    .into_iter()
+                    .find(|e| e.id.title == title)
+                {
+                    Some(entry) => Some(entry.id),
+                    None => store.latest_finished(&title)?.map(|e| e.id),
+                },
+                None => store.unfinished(1)?.pop().map(|e| e.id),
+            };
+            match id {
+                Some(id) => {
+                    store.add_tags(&id, &add)?;
+                    store.remove_tags(&id, &remove)?;
+                    output.print(&json!({"status": "tagged", "id": id}), || {
+                        println!("(Tagged: {})", &id.title)
+                    });
+                }
+                None => output.print(&json!({"status": "not_found"}), || println!("(Not found)")),
+            }
+        }
+        Commands::Tags => {
+            let store = new_sqlite_store(&store_file)?;
+            let tags = store.tags()?;
+            output.print(&tags, || print_titles(&tags, false));
+        }
+        Commands::Edit {
+            title,
+            at,
+            rename,
+            start,
+            end,
+            notes,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match select_finished(&store, title, at, recent_limit)? {
+                Some(entry) => {
+                    let mut updated = entry.clone();
+                    if let Some(title) = rename {
+                        updated.id.title = title.into();
+                    }
+                    updated.id.start = start.unwrap_or(updated.id.start);
+                    updated.end = end.unwrap_or(updated.end);
+                    if !notes.is_empty() {
+                        updated.notes = read_notes(notes).into();
+                    }
+
+                    if store.update_entry(&entry.id, &updated)? {
+                        output.print(&json!({"status": "updated", "entry": updated}), || {
+                            print!("(Updated)\n{updated}")
+                        });
+                    } else {
+                        output.print(&json!({"status": "not_found"}), || println!("(Not found)"));
+                    }
+                }
+                None => output.print(&json!({"status": "not_found"}), || println!("(Not found)")),
+            }
+        }
+        Commands::Delete { title, at, yes } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match select_finished(&store, title, at, recent_limit)? {
+                Some(entry) => {
+                    if output == Output::Text {
+                        print!("{entry}");
+                    } else if !yes {
+                        eprint!("{entry}");
+                    }
+                    if yes || confirm("Delete this entry?") {
+                        if store.delete_entry(&entry.id)? {
+                            output.print(&json!({"status": "deleted", "entry": entry}), || {
+                                println!("(Deleted)")
+                            });
+                        } else {
+                            output
+                                .print(&json!({"status": "not_found"}), || println!("(Not found)"));
+                        }
+                    }
+                }
+                None => output.print(&json!({"status": "not_found"}), || println!("(Not found)")),
+            }
+        }
+        Commands::Check { fix, stale_hours } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let stale_after = chrono::Duration::hours(stale_hours.into());
+            let report = store.integrity_report(stale_after)?;
+            output.print(&report, || print!("{report}"));
+            if fix && !report.is_clean() {
+                fix_integrity(&mut store, report, stale_after)?;
+            }
+        }
+        Commands::Mode { mode } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match mode {
+                Some(mode) => {
+                    store.set_tracking_mode(mode)?;
+                    output.print(&json!({"status": "changed", "mode": mode}), || {
+                        println!("(Changed to {mode})")
+                    });
+                }
+                None => {
+                    let mode = store.tracking_mode()?;
+                    output.print(&mode, || println!("{mode}"));
+                }
+            }
+        }
+        Commands::MaxLength { length } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match length.as_deref() {
+                Some("off") => {
+                    store.set_max_entry_length(None)?;
+                    output.print(&json!({"status": "disabled"}), || println!("(Disabled)"));
+                }
+                Some(length) => {
+                    let length = clocking::timeparse::parse_duration(length)?;
+                    store.set_max_entry_length(Some(length))?;
+                    let value = json!({"status": "changed", "seconds": length.num_seconds()});
+                    output.print(&value, || {
+                        println!("(Changed to {})", format_duration(&length))
+                    });
+                }
+                None => {
+                    let length = store.max_entry_length()?;
+                    output.print(&length.map(|l| l.num_seconds()), || match length {
+                        Some(length) => println!("{}", format_duration(&length)),
+                        None => println!("off"),
+                    });
+                }
+            }
+        }
+        Commands::Workday { hours, weekday } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let mut workday = store.workday()?;
+            match hours {
+                Some(hours) => {
+                    let hours = Workday::parse_hours(&hours)?;
+                    let weekdays = if weekday.is_empty() {
+                        WEEKDAYS.to_vec()
+                    } else {
+                        weekday
+                    };
+                    for weekday in weekdays.iter() {
+                        workday.set_hours(*weekday, hours)?;
+                    }
+                    store.set_workday(&workday)?;
+                    output.print(&json!({"status": "changed", "workday": workday}), || {
+                        println!("(Changed)")
+                    });
+                    let overridden: Vec<String> = config
+                        .workday
+                        .overridden()
+                        .into_iter()
+                        .filter(|weekday| weekdays.contains(weekday))
+                        .map(|weekday| weekday.to_string())
+                        .collect();
+                    if !overridden.is_empty() {
+                        eprintln!(
+                            "Warning: hours of {} are overridden by [workday] of the config file.",
+                            overridden.join(", ")
+                        );
+                    }
+                }
+                None => {
+                    let overridden = config.workday.overridden();
+                    let workday = config.workday.apply(workday)?;
+                    output.print(&workday, || {
+                        for weekday in WEEKDAYS {
+                            let source = if overridden.contains(&weekday) {
+                                " (config)"
+                            } else {
+                                ""
+                            };
+                            match workday.hours(weekday) {
+                                Some((start, end)) => println!(
+                                    "{weekday}: {}-{}{source}",
+                                    start.format("%H:%M"),
+                                    end.format("%H:%M")
+                                ),
+                                None => println!("{weekday}: off{source}"),
+                            }
+                        }
+                    })
+                }
+            }
+        }
+        Commands::Export {
+            format,
+            from,
+            to,
+            out,
+        } => {
+            let store = new_sqlite_store(&store_file)?;
+            let from = from.unwrap_or_else(|| "1970-01-01".to_string());
+            let to = to.unwrap_or_else(|| Local::now().date_naive().to_string());
+            let entries = store.finished_overlapping_dates(&from, &to, None)?;
+            let writer: Box<dyn Write> = match out {
+                Some(out) => Box::new(
+                    std::fs::File::create(out)
+                        .map_err(|e| errors::Error::UnderlyingError(e.to_string()))?,
+                ),
+                None => Box::new(io::stdout()),
+            };
+            match format {
+                ExportFormat::Csv => exchange::write_csv(writer, &entries)?,
+                ExportFormat::Ics => exchange::write_ics(writer, &entries)?,
+            }
+        }
+        Commands::Import {
+            files,
+            format,
+            tags_into,
+            dry_run,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let importer = format.importer(tags_into.into());
+            let mut entries = vec![];
+            let mut skipped = vec![];
+            for file in files.iter() {
+                let mut reader: Box<dyn io::Read> = if file.as_os_str() == "-" {
+                    Box::new(io::stdin())
+                } else {
+                    Box::new(
+                        std::fs::File::open(file)
+                            .map_err(|e| errors::Error::UnderlyingError(e.to_string()))?,
+                    )
+                };
+                let parsed = importer.parse(&mut reader)?;
+                entries.extend(parsed.entries);
+                skipped.extend(
+                    parsed
+                        .skipped
+                        .into_iter()
+                        .map(|desc| format!("{}: {desc}", file.display())),
+                );
+            }
+            let mut report = store.import_finished(&entries, dry_run)?;
+            report.skipped = skipped;
+            output.print(&report, || print!("{report}"));
+        }
+        Commands::Rate {
+            project,
+            client,
+            hourly,
+            remove,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            match (project, client, hourly) {
+                (Some(project), _, _) if remove => {
+                    if store.remove_rate(&project)? {
+                        output.print(&json!({"status": "removed"}), || println!("(Removed)"));
+                    } else {
+                        output.print(&json!({"status": "not_found"}), || println!("(Not found)"));
+                    }
+                }
+                (Some(project), Some(client), Some(cents_per_hour)) => {
+                    let rate = Rate {
+                        project,
+                        client,
+                        cents_per_hour,
+                    };
+                    store.set_rate(&rate)?;
+                    output.print(&json!({"status": "changed", "rate": rate}), || {
+                        println!("(Changed)")
+                    });
+                }
+                (Some(project), _, _) => {
+                    let rates = store.rates()?;
+                    match Rate::find(&rates, &project) {
+                        Some(rate) => output.print(rate, || print!("{rate}")),
+                        None => output
+                            .print(&json!({"status": "not_found"}), || println!("(Not found)")),
+                    }
+                }
+                (None, _, _) => {
+                    let rates = store.rates()?;
+                    output.print(&rates, || rates.iter().for_each(|rate| print!("{rate}")));
+                }
+            }
+        }
+        Commands::Invoice {
+            client,
+            month,
+            html,
+            round,
+            round_per,
+        } => {
+            let rounding = round.map(|rounding| Rounding {
+                scope: round_per,
+                ..rounding
+            });
+            let store = new_sqlite_store(&store_file)?;
+            let from = month.unwrap_or_else(|| {
+                let this_month = Local::now().date_naive().with_day(1).unwrap();
+                this_month.pred_opt().unwrap().with_day(1).unwrap()
+            });
+            let to = from
+                .checked_add_months(chrono::Months::new(1))
+                .and_then(|next| next.pred_opt())
+                .unwrap();
+            let entries = store.finished_by_date_str(&from.to_string(), &to.to_string(), None)?;
+            let view = clocking::views::BillingView::new(&entries, &store.rates()?, rounding)
+                .only_client(&client);
+            let invoice = if html {
+                view.invoice_html(&client, from, to)
+            } else {
+                view.invoice(&client, from, to)
+            };
+            match invoice {
+                Some(invoice) => output.print(&view, || print!("{invoice}")),
+                None => output.print(&json!({"status": "not_found"}), || {
+                    println!("(Nothing billed to {client} in {})", from.format("%Y-%m"))
+                }),
+            }
+        }
+        Commands::Goal {
+            subject,
+            target,
+            cap,
+            remove,
+            per,
+            week_start,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            let set = match (target, cap) {
+                (Some(target), _) => Some((GoalKind::Target, target)),
+                (_, Some(cap)) => Some((GoalKind::Cap, cap)),
+                _ => None,
+            };
+            match (subject, set, remove) {
+                (Some(subject), _, Some(kind)) => {
+                    if store.remove_goal(&subject, per, kind)? {
+                        output.print(&json!({"status": "removed"}), || println!("(Removed)"));
+                    } else {
+                        output.print(&json!({"status": "not_found"}), || println!("(Not found)"));
+                    }
+                }
+                (Some(subject), Some((kind, duration)), None) => {
+                    let goal = Goal {
+                        subject,
+                        period: per,
+                        kind,
+                        duration,
+                    };
+                    store.set_goal(&goal)?;
+                    output.print(&json!({"status": "changed", "goal": goal}), || {
+                        println!("(Changed)")
+                    });
+                }
+                (subject, _, _) => {
+                    let goals: Vec<Goal> = store
+                        .goals()?
+                        .into_iter()
+                        .filter(|goal| subject.as_ref().is_none_or(|s| goal.subject == *s))
+                        .collect();
+                    let week_start = week_start.unwrap_or(config.report.week_start);
+                    let view = goal_progress(&store, &goals, week_start)?;
+                    output.print(&view, || print!("{view}"));
+                }
+            }
+        }
         Commands::Titles { number, index } => {
-            let store = new_sqlite_store(&store_file);
-            print_titles(&store.recent_titles(number)?, index);
+            let store = new_sqlite_store(&store_file)?;
+            let titles = store.recent_titles(number)?;
+            output.print(&titles, || print_titles(&titles, index));
         }
-        Commands::Server { port, addr } => {
+        Commands::Server {
+            port,
+            addr,
+            skip_corrupt,
+        } => {
+            let mut store = new_sqlite_store(&store_file)?;
+            store.skip_corrupt(skip_corrupt);
             // TODO: understand why T is Send makes Mutex<T> both Send and Sync
-            let store = Box::new(Mutex::new(new_sqlite_store(&store_file)));
-            let _ = clocking::server::launch_server(
-                port.unwrap_or(8080),
-                addr.unwrap_or_else(|| std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1))),
-                None,
-                store,
-            )
-            .await;
+            let store = Box::new(Mutex::new(store));
+            config.server.port = port.unwrap_or(config.server.port);
+            config.server.addr = addr.unwrap_or(config.server.addr);
+            let _ = clocking::server::launch_server(config, None, store).await;
+        }
+        Commands::Config { .. } => unreachable!("config is shown before opening the store"),
+    }
+
+    Ok(())
+}
+
+fn parse_time(input: &str) -> Result<DateTime<Utc>, errors::Error> {
+    clocking::timeparse::parse_datetime(input, &Local::now())
+}
+
+fn parse_clock_time(input: &str) -> Result<NaiveTime, chrono::ParseError> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+}
+
+/// Parse a month like '2026-09' as its first day.
+fn parse_month(input: &str) -> Result<NaiveDate, chrono::ParseError> {
+    NaiveDate::parse_from_str(&format!("{input}-01"), "%Y-%m-%d")
+}
+
+/// Parse an amount like '120' or '99.50' in cents.
+fn parse_cents(input: &str) -> Result<i64, &'static str> {
+    const INVALID: &str = "amount should be like '120' or '99.50'";
+    let (whole, fraction) = input.trim().split_once('.').unwrap_or((input.trim(), "0"));
+    if whole.is_empty() || fraction.is_empty() || fraction.len() > 2 {
+        return Err(INVALID);
+    }
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) {
+        return Err(INVALID);
+    }
+    let whole: i64 = whole.parse().map_err(|_| INVALID)?;
+    let fraction: i64 = format!("{fraction:0<2}").parse().map_err(|_| INVALID)?;
+    whole
+        .checked_mul(100)
+        .and_then(|cents| cents.checked_add(fraction))
+        .ok_or(INVALID)
+}
+
+/// Progress of `goals` on today and this week, counting ongoing entries until now.
+fn goal_progress(
+    store: &impl ClockingStore,
+    goals: &[Goal],
+    week_start: Weekday,
+) -> Result<clocking::views::GoalProgressView, errors::Error> {
+    let today = Local::now().date_naive();
+    let since = today - chrono::Duration::days(7);
+    let mut entries = store.finished_by_date_str(&since.to_string(), &today.to_string(), None)?;
+    let now = Utc::now();
+    entries.extend(
+        store
+            .unfinished(usize::MAX)?
+            .iter()
+            .map(|entry| entry.finished_at(now)),
+    );
+    Ok(clocking::views::GoalProgressView::new(
+        goals, &entries, today, week_start,
+    ))
+}
+
+/// Warn on stderr about caps exceeded by entries of `title` with `tags` already.
+fn warn_exceeded_caps(store: &impl ClockingStore, title: &str, tags: &[String], config: &Config) {
+    let progress = store
+        .goals()
+        .and_then(|goals| goal_progress(store, &goals, config.report.week_start));
+    match progress {
+        Ok(progress) => {
+            for (goal, spent) in progress.exceeded_caps(title, tags) {
+                eprintln!(
+                    "(Warning: {} spent on {} this {}, over the cap of {})",
+                    format_duration(&spent),
+                    goal.subject,
+                    goal.period,
+                    format_duration(&goal.duration)
+                );
+            }
         }
+        Err(e) => eprintln!("{e}"),
     }
+}
+
+/// Prompt to finish overdue unfinished entries at a chosen time, which are most likely forgotten.
+///
+/// Prompts go to stderr, only warns there if not `interactive`.
+fn prompt_overdue(store: &mut impl ClockingStore, interactive: bool) -> Result<(), errors::Error> {
+    let overdue = store.overdue()?;
+    let max_length = match store.max_entry_length()? {
+        Some(length) if !overdue.is_empty() => length,
+        _ => return Ok(()),
+    };
+
+    for entry in overdue {
+        eprint!(
+            "(Running longer than {})\n{entry}",
+            format_duration(&max_length)
+        );
+        if !interactive {
+            continue;
+        }
 
+        let capped = entry.id.start + max_length;
+        eprint!(
+            "Finish at (e.g. '17:40', 'c' for {}, empty to keep running): ",
+            capped.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        io::stderr().flush().unwrap();
+        let end = match read_or_panic().trim() {
+            "" => continue,
+            "c" => capped,
+            input => match parse_time(input) {
+                Ok(end) => end,
+                Err(err) => {
+                    eprintln!("{err}, skipped.");
+                    continue;
+                }
+            },
+        };
+
+        if end <= entry.id.start {
+            eprintln!("End time must be after start, skipped.");
+        } else if store.try_finish_entry(&entry.id, &end, "")? {
+            eprintln!("(Finished: {})", entry.id.title);
+        }
+    }
     Ok(())
 }
 
+/// Join notes arguments as lines, single value '-' means read from stdin.
+fn read_notes(notes: Vec<String>) -> String {
+    if notes.len() == 1 && notes[0] == "-" {
+        read_to_end()
+    } else {
+        notes.join("\n")
+    }
+}
+
+/// Select an unfinished entry of `title` if given, among those accepted by `wanted`.
+///
+/// Chooses interactively if multiple entries are found.
+fn select_unfinished<'a>(
+    store: &impl ClockingStore,
+    title: Option<&str>,
+    wanted: impl Fn(&UnfinishedEntry) -> bool,
+) -> Result<Option<UnfinishedEntry<'a>>, errors::Error> {
+    let mut candidates = store.unfinished_of(title)?;
+    candidates.retain(|e| wanted(e));
+    Ok(choose_entry(candidates))
+}
+
+/// Select a finished entry of `title` running at `at`, or the latest one of `title` if `at` is None.
+///
+/// Chooses interactively when `title` is None but needed, or multiple entries are found.
+fn select_finished<'a>(
+    store: &impl ClockingStore,
+    title: Option<String>,
+    at: Option<DateTime<Utc>>,
+    recent_limit: usize,
+) -> Result<Option<FinishedEntry<'a>>, errors::Error> {
+    match at {
+        Some(at) => {
+            let candidates: Vec<FinishedEntry> = store
+                .finished_covering(&at)?
+                .into_iter()
+                .filter(|e| title.as_ref().is_none_or(|t| e.id.title == *t))
+                .collect();
+            Ok(choose_entry(candidates))
+        }
+        None => match handle_title(title, &store.recent_titles(recent_limit)?) {
+            Ok(title) => store.latest_finished(&title),
+            Err(err) => {
+                eprintln!("Error reading or choosing title: {err}.");
+                Ok(None)
+            }
+        },
+    }
+}
+
+/// Interactively fix problems of `report`.
+///
+/// Overlaps are fixed one pair at a time and checked again after each fix,
+/// since trimming one entry may resolve or change its other overlaps.
+fn fix_integrity(
+    store: &mut impl ClockingStore,
+    report: clocking::types::IntegrityReport,
+    stale_after: chrono::Duration,
+) -> Result<(), errors::Error> {
+    for entry in report.non_positive {
+        eprint!("\n{entry}");
+        if confirm("Delete this entry not ending after start?") && store.delete_entry(&entry.id)? {
+            eprintln!("(Deleted)");
+        }
+    }
+
+    let mut skipped = vec![];
+    loop {
+        let overlaps = store.integrity_report(stale_after)?.overlaps;
+        let (first, second) = match overlaps.into_iter().find(|pair| !skipped.contains(pair)) {
+            Some(pair) => pair,
+            None => break,
+        };
+
+        eprint!("\n1: {first}2: {second}");
+        let contained = second.end <= first.end;
+        if contained {
+            eprint!("(s)plit 1 around 2, (d)elete 2, or skip [s/d/N]: ");
+        } else {
+            eprint!("Trim (e)nd of 1, trim (s)tart of 2, or skip [e/s/N]: ");
+        }
+        io::stderr().flush().unwrap();
+        let fixed = match (contained, read_or_panic().trim()) {
+            (true, "s") => split_around(store, &first, &second),
+            (true, "d") => store.delete_entry(&second.id),
+            (false, "e") => store.update_entry(
+                &first.id,
+                &FinishedEntry {
+                    end: second.id.start,
+                    ..first.clone()
+                },
+            ),
+            (false, "s") => store.update_entry(
+                &second.id,
+                &FinishedEntry {
+                    id: EntryId {
+                        start: first.end,
+                        ..second.id.clone()
+                    },
+                    ..second.clone()
+                },
+            ),
+            _ => {
+                skipped.push((first, second));
+                continue;
+            }
+        };
+        match fixed {
+            Ok(true) => eprintln!("(Fixed)"),
+            Ok(false) => eprintln!("(Changed already, skipped)"),
+            Err(err) => {
+                eprintln!("Failed to fix: {err}");
+                skipped.push((first, second));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Split `outer` into the parts before and after `inner`, parts of zero length are dropped.
+fn split_around(
+    store: &mut impl ClockingStore,
+    outer: &FinishedEntry,
+    inner: &FinishedEntry,
+) -> Result<bool, errors::Error> {
+    let before = FinishedEntry {
+        end: inner.id.start,
+        ..outer.clone()
+    };
+    let after = FinishedEntry {
+        id: EntryId {
+            start: inner.end,
+            ..outer.id.clone()
+        },
+        ..outer.clone()
+    };
+
+    match (before.end > before.id.start, after.end > after.id.start) {
+        (true, true) => store.split_entry(&outer.id, &inner.id.start, &inner.end),
+        (true, false) => store.update_entry(&outer.id, &before),
+        (false, true) => store.update_entry(&outer.id, &after),
+        (false, false) => store.delete_entry(&outer.id),
+    }
+}
+
+fn choose_entry<T: std::fmt::Display>(mut entries: Vec<T>) -> Option<T> {
+    if entries.len() <= 1 {
+        return entries.pop();
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        eprint!("{}: {entry}", i + 1);
+    }
+    eprint!("Choose by index (default 1): ");
+    io::stderr().flush().unwrap();
+    let input = read_or_panic();
+    match input.trim() {
+        "" => Some(entries.swap_remove(0)),
+        input => match input.parse::<usize>() {
+            Ok(i) if i <= entries.len() && i > 0 => Some(entries.swap_remove(i - 1)),
+            _ => {
+                eprintln!("Invalid index: {input}.");
+                None
+            }
+        },
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    eprint!("{prompt} [y/N]: ");
+    io::stderr().flush().unwrap();
+    matches!(read_or_panic().trim(), "y" | "Y" | "yes")
+}
+
 fn handle_title(title: Option<String>, recent_titles: &[String]) -> Result<String, String> {
     let empty_title_err = "Empty title".to_string();
     title
@@ -211,8 +1503,8 @@ fn handle_title(title: Option<String>, recent_titles: &[String]) -> Result<Strin
 fn read_title(recent_titles: &[String]) -> Result<String, String> {
     if recent_titles.is_empty() {
         // read title from input
-        print!("Input Title: ");
-        io::stdout().flush().unwrap();
+        eprint!("Input Title: ");
+        io::stderr().flush().unwrap();
         let input = read_or_panic();
         let input = input.trim();
         if input.is_empty() {
@@ -222,9 +1514,11 @@ fn read_title(recent_titles: &[String]) -> Result<String, String> {
         }
     } else {
         // choose from recent titles
-        print_titles(recent_titles, true);
-        print!("Choose by index (default 1): ");
-        io::stdout().flush().unwrap();
+        for (i, t) in recent_titles.iter().enumerate() {
+            eprintln!("{}: {t}", i + 1);
+        }
+        eprint!("Choose by index (default 1): ");
+        io::stderr().flush().unwrap();
         let input = read_or_panic();
         let input = input.trim();
         if input.is_empty() {
@@ -265,7 +1559,7 @@ fn read_or_panic() -> String {
     let mut buf = String::new();
     match io::stdin().read_line(&mut buf) {
         Ok(n) if n > 0 => buf,
-        Ok(n) if n == 0 => buf,
+        Ok(0) => buf,
         Ok(n) => panic!("Unexpected read bytes: {n}"),
         Err(e) => panic!("Unexpected read error: {e}"),
     }
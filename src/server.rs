@@ -1,14 +1,23 @@
 //! An optional http interface, requires feature **http**.
 #[cfg(feature = "http")]
-use crate::{types::EntryId, views, ClockingStore};
+use crate::{
+    config::Config,
+    errors::Error,
+    exchange,
+    filter::Filter,
+    timeparse,
+    types::{EntryId, FinishedEntry, RoundScope, Rounding, TrackingMode, UnfinishedEntry},
+    views, ClockingStore,
+};
 use rocket::{
-    get,
+    delete, get,
     http::{ContentType, Status},
-    post,
+    post, put,
     serde::json::Json,
     State,
 };
 use rust_embed::RustEmbed;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Mutex;
 
@@ -18,15 +27,15 @@ struct Asset;
 
 type ServerConfig = Box<Mutex<dyn ClockingStore + Send>>;
 
+/// Serve `store` at the address of `settings`, which are managed for the limits and defaults.
 pub async fn launch_server(
-    port: u16,
-    address: std::net::IpAddr,
+    settings: Config,
     mount_base: Option<&str>,
     store: ServerConfig,
 ) -> Result<rocket::Rocket<rocket::Ignite>, rocket::Error> {
     let config = rocket::config::Config {
-        port,
-        address,
+        port: settings.server.port,
+        address: settings.server.addr,
         ..rocket::config::Config::default()
     };
 
@@ -36,6 +45,7 @@ pub async fn launch_server(
     };
     let rocket = rocket::custom(&config)
         .manage(store)
+        .manage(settings)
         .mount(
             api_mount,
             rocket::routes![
@@ -44,7 +54,17 @@ pub async fn launch_server(
                 api_unfinished,
                 api_start,
                 api_finish,
+                api_finish_entry,
+                api_mode,
+                api_switch,
+                api_pause,
+                api_pause_entry,
+                api_resume,
+                api_resume_entry,
                 api_report_by_date,
+                api_calendar,
+                api_update_entry,
+                api_delete_entry,
             ],
         )
         .mount(root_mount, rocket::routes![index, favicon, anyfile,]);
@@ -53,10 +73,10 @@ pub async fn launch_server(
 }
 
 #[get("/recent")]
-fn api_recent(config: &State<ServerConfig>) -> Json<Vec<String>> {
+fn api_recent(config: &State<ServerConfig>, settings: &State<Config>) -> Json<Vec<String>> {
     let store = config.lock().unwrap();
     // TODO: remove unwrap
-    Json(store.recent_titles(5).unwrap())
+    Json(store.recent_titles(settings.limits.api_recent).unwrap())
 }
 
 #[get("/latest/<title>")]
@@ -67,32 +87,66 @@ fn api_latest(title: &str, config: &State<ServerConfig>) -> String {
         .latest_finished(title)
         .unwrap()
         .map(|entity| entity.html_segment())
-        .unwrap_or_else(String::new)
+        .unwrap_or_default()
+}
+
+/// An unfinished entry flagged whether it's overdue, see [`ClockingStore::overdue`].
+#[derive(Serialize)]
+struct UnfinishedStatus<'a> {
+    #[serde(flatten)]
+    entry: UnfinishedEntry<'a>,
+    overdue: bool,
 }
 
 #[get("/unfinished")]
-fn api_unfinished(config: &State<ServerConfig>) -> Json<Vec<EntryId>> {
+fn api_unfinished(
+    config: &State<ServerConfig>,
+    settings: &State<Config>,
+) -> Result<Json<Vec<UnfinishedStatus<'static>>>, (Status, String)> {
     let store = config.lock().unwrap();
-    // TODO: remove unwrap
-    let r: Vec<EntryId> = store
-        .unfinished(10)
-        .unwrap()
+    let error = |err: Error| (error_status(&err), err.to_string());
+    let overdue = store.overdue().map_err(error)?;
+    let r = store
+        .unfinished(settings.limits.api_unfinished)
+        .map_err(error)?
         .into_iter()
-        .map(|x| x.id)
+        .map(|entry| UnfinishedStatus {
+            overdue: overdue.contains(&entry),
+            entry,
+        })
         .collect();
-    Json(r)
+    Ok(Json(r))
 }
 
-#[post("/start/<title>")]
-fn api_start(title: &str, config: &State<ServerConfig>) -> Status {
+#[post("/start/<title>?<tag>&<at>")]
+fn api_start(
+    title: &str,
+    tag: Vec<String>,
+    at: Option<&str>,
+    config: &State<ServerConfig>,
+) -> Status {
     if title.is_empty() {
-        Status::BadRequest
-    } else {
-        let mut store = config.lock().unwrap();
-        match store.start(title) {
-            Ok(_) => Status::Ok,
-            Err(_) => Status::InternalServerError,
-        }
+        return Status::BadRequest;
+    }
+    let start = match at.map(|at| timeparse::parse_datetime(at, &chrono::Local::now())) {
+        Some(Ok(start)) => start,
+        Some(Err(_)) => return Status::BadRequest,
+        None => chrono::Utc::now(),
+    };
+
+    let mut store = config.lock().unwrap();
+    let entry = UnfinishedEntry {
+        id: EntryId {
+            title: title.into(),
+            start,
+        },
+        notes: "".into(),
+        tags: tag,
+        pauses: vec![],
+    };
+    match store.start_entry_since(&entry) {
+        Ok(_) => Status::Ok,
+        Err(err) => error_status(&err),
     }
 }
 
@@ -105,28 +159,214 @@ fn api_finish(notes: String, config: &State<ServerConfig>) -> Status {
     }
 }
 
-#[get("/report-by-date/<start>/<end>?<view_type>")]
+#[post("/finish/<title>/<start>", data = "<notes>")]
+fn api_finish_entry(
+    title: &str,
+    start: &str,
+    notes: String,
+    config: &State<ServerConfig>,
+) -> Status {
+    match path_entry_id(title, start) {
+        Some(id) => {
+            let mut store = config.lock().unwrap();
+            match store.try_finish_entry(&id, &chrono::Utc::now(), &notes) {
+                Ok(true) => Status::Ok,
+                Ok(false) => Status::NotFound,
+                Err(err) => error_status(&err),
+            }
+        }
+        None => Status::BadRequest,
+    }
+}
+
+#[get("/mode")]
+fn api_mode(config: &State<ServerConfig>) -> (Status, Json<Option<TrackingMode>>) {
+    let store = config.lock().unwrap();
+    match store.tracking_mode() {
+        Ok(mode) => (Status::Ok, Json(Some(mode))),
+        Err(err) => (error_status(&err), Json(None)),
+    }
+}
+
+#[post("/switch/<title>?<tag>", data = "<notes>")]
+fn api_switch(
+    title: &str,
+    tag: Vec<String>,
+    notes: String,
+    config: &State<ServerConfig>,
+) -> Status {
+    if title.is_empty() {
+        return Status::BadRequest;
+    }
+
+    let mut store = config.lock().unwrap();
+    let entry = UnfinishedEntry {
+        id: EntryId {
+            title: title.into(),
+            start: chrono::Utc::now(),
+        },
+        notes: "".into(),
+        tags: tag,
+        pauses: vec![],
+    };
+    match store.switch_to_entry(&entry, &notes) {
+        Ok(_) => Status::Ok,
+        Err(err) => error_status(&err),
+    }
+}
+
+#[post("/pause")]
+fn api_pause(config: &State<ServerConfig>) -> Status {
+    let mut store = config.lock().unwrap();
+    match store.try_pause_any(&chrono::Utc::now()) {
+        Ok(Some(_)) => Status::Ok,
+        Ok(None) => Status::NotFound,
+        Err(err) => error_status(&err),
+    }
+}
+
+#[post("/pause/<title>/<start>")]
+fn api_pause_entry(title: &str, start: &str, config: &State<ServerConfig>) -> Status {
+    match path_entry_id(title, start) {
+        Some(id) => {
+            let mut store = config.lock().unwrap();
+            match store.try_pause_entry(&id, &chrono::Utc::now()) {
+                Ok(true) => Status::Ok,
+                Ok(false) => Status::NotFound,
+                Err(err) => error_status(&err),
+            }
+        }
+        None => Status::BadRequest,
+    }
+}
+
+#[post("/resume")]
+fn api_resume(config: &State<ServerConfig>) -> Status {
+    let mut store = config.lock().unwrap();
+    match store.try_resume_any(&chrono::Utc::now()) {
+        Ok(Some(_)) => Status::Ok,
+        Ok(None) => Status::NotFound,
+        Err(err) => error_status(&err),
+    }
+}
+
+#[post("/resume/<title>/<start>")]
+fn api_resume_entry(title: &str, start: &str, config: &State<ServerConfig>) -> Status {
+    match path_entry_id(title, start) {
+        Some(id) => {
+            let mut store = config.lock().unwrap();
+            match store.try_resume_entry(&id, &chrono::Utc::now()) {
+                Ok(true) => Status::Ok,
+                Ok(false) => Status::NotFound,
+                Err(err) => error_status(&err),
+            }
+        }
+        None => Status::BadRequest,
+    }
+}
+
+#[get(
+    "/report-by-date/<start>/<end>?<view_type>&<filter>&<tag>&<week_start>&<split_overlaps>&<day_start>&<day_end>&<depth>&<round>&<round_per>"
+)]
+#[allow(clippy::too_many_arguments)]
 fn api_report_by_date(
     start: &str,
     end: &str,
     view_type: &str,
+    filter: Option<&str>,
+    tag: Vec<String>,
+    week_start: Option<&str>,
+    split_overlaps: Option<bool>,
+    day_start: Option<&str>,
+    day_end: Option<&str>,
+    depth: Option<usize>,
+    round: Option<&str>,
+    round_per: Option<&str>,
     config: &State<ServerConfig>,
+    settings: &State<Config>,
 ) -> (Status, String) {
+    let week_start = match week_start.map(str::parse::<chrono::Weekday>) {
+        Some(Ok(w)) => w,
+        Some(Err(err)) => return (Status::BadRequest, err.to_string()),
+        None => settings.report.week_start,
+    };
+    let filter = match filter
+        .filter(|f| !f.trim().is_empty())
+        .map(str::parse::<Filter>)
+    {
+        Some(Ok(f)) => Some(f),
+        Some(Err(err)) => return (Status::BadRequest, err.to_string()),
+        None => None,
+    };
+    let filter = Filter::with_tags(filter, &tag);
+    let parse_time = |t: Option<&str>| {
+        t.map(|t| chrono::NaiveTime::parse_from_str(t, "%H:%M"))
+            .transpose()
+    };
+    let (day_start, day_end) = match (parse_time(day_start), parse_time(day_end)) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(err), _) | (_, Err(err)) => return (Status::BadRequest, err.to_string()),
+    };
+    let rounding = match (
+        round
+            .filter(|r| !r.trim().is_empty())
+            .map(str::parse::<Rounding>),
+        round_per.map(str::parse::<RoundScope>).transpose(),
+    ) {
+        (Some(Ok(rounding)), Ok(scope)) => Some(Rounding {
+            scope: scope.unwrap_or_default(),
+            ..rounding
+        }),
+        (None, Ok(_)) => None,
+        (Some(Err(err)), _) | (_, Err(err)) => return (Status::BadRequest, err.to_string()),
+    };
+
     let store = config.lock().unwrap();
-    match store.finished_by_date_str(start, end) {
+    match store.finished_by_date_str(start, end, filter.as_ref()) {
         Ok(entries) => {
+            let entries = if split_overlaps.unwrap_or(false) {
+                views::split_overlaps(&entries)
+            } else {
+                entries
+            };
             let resp = if view_type == "daily" {
-                let view = views::DailySummaryView::new(&entries);
+                let view = views::DailySummaryView::new(&entries, rounding);
                 view.to_string()
             } else if view_type == "detail" {
-                let view = views::EntryDetailView::new(&entries);
+                let view = views::EntryDetailView::new(&entries, rounding);
                 view.to_string()
             } else if view_type == "dist" {
-                let view = views::DailyDistributionView::new(&entries);
+                match store
+                    .workday()
+                    .and_then(|workday| settings.workday.apply(workday))
+                    .and_then(|workday| workday.with_bounds(day_start, day_end))
+                {
+                    Ok(workday) => {
+                        views::DailyDistributionView::new(&entries, &workday).to_string()
+                    }
+                    Err(err) => return (error_status(&err), err.to_string()),
+                }
+            } else if view_type == "weekly" {
+                let view = views::WeeklySummaryView::new(&entries, week_start, rounding);
+                view.to_string()
+            } else if view_type == "weekly_detail" {
+                let view = views::WeeklyDetailView::new(&entries, week_start, rounding);
+                view.to_string()
+            } else if view_type == "monthly" {
+                let view = views::MonthlySummaryView::new(&entries, rounding);
+                view.to_string()
+            } else if view_type == "monthly_detail" {
+                let view = views::MonthlyDetailView::new(&entries, rounding);
+                view.to_string()
+            } else if view_type == "tag" {
+                let view = views::TagSummaryView::new(&entries);
+                view.to_string()
+            } else if view_type == "tree" {
+                let view = views::ProjectTreeView::new(&entries, depth.filter(|d| *d > 0));
                 view.to_string()
             } else {
                 // default to view type 'daily_detail'
-                let view = views::DailyDetailView::new(&entries);
+                let view = views::DailyDetailView::new(&entries, rounding);
                 view.to_string()
             };
 
@@ -136,6 +376,86 @@ fn api_report_by_date(
     }
 }
 
+/// Finished entries started within local dates `[from, to]` as iCalendar events for subscription.
+///
+/// Both are 'YYYY-MM-DD', default to the earliest and today.
+#[get("/calendar.ics?<from>&<to>")]
+fn api_calendar(
+    from: Option<&str>,
+    to: Option<&str>,
+    config: &State<ServerConfig>,
+) -> (Status, (ContentType, String)) {
+    let today = chrono::Local::now().date_naive().to_string();
+    let store = config.lock().unwrap();
+    let mut ics = vec![];
+    match store
+        .finished_overlapping_dates(from.unwrap_or("1970-01-01"), to.unwrap_or(&today), None)
+        .and_then(|entries| exchange::write_ics(&mut ics, &entries))
+    {
+        Ok(()) => (
+            Status::Ok,
+            (
+                ContentType::Calendar,
+                String::from_utf8_lossy(&ics).to_string(),
+            ),
+        ),
+        Err(err) => (error_status(&err), (ContentType::Plain, err.to_string())),
+    }
+}
+
+/// Parse entry id from path segments `<title>/<start>`, `start` is in RFC 3339.
+fn path_entry_id<'a>(title: &'a str, start: &str) -> Option<EntryId<'a>> {
+    chrono::DateTime::parse_from_rfc3339(start)
+        .ok()
+        .map(|start| EntryId {
+            title: title.into(),
+            start: start.with_timezone(&chrono::Utc),
+        })
+}
+
+fn error_status(err: &Error) -> Status {
+    match err {
+        Error::InvalidInput(_) | Error::InvalidFilter(_) => Status::BadRequest,
+        Error::DuplicateEntry | Error::UnfinishedExists(_) => Status::Conflict,
+        _ => Status::InternalServerError,
+    }
+}
+
+#[put("/entry/<title>/<start>", data = "<updated>")]
+fn api_update_entry(
+    title: &str,
+    start: &str,
+    updated: Json<FinishedEntry>,
+    config: &State<ServerConfig>,
+) -> Status {
+    match path_entry_id(title, start) {
+        Some(id) => {
+            let mut store = config.lock().unwrap();
+            match store.update_entry(&id, &updated) {
+                Ok(true) => Status::Ok,
+                Ok(false) => Status::NotFound,
+                Err(err) => error_status(&err),
+            }
+        }
+        None => Status::BadRequest,
+    }
+}
+
+#[delete("/entry/<title>/<start>")]
+fn api_delete_entry(title: &str, start: &str, config: &State<ServerConfig>) -> Status {
+    match path_entry_id(title, start) {
+        Some(id) => {
+            let mut store = config.lock().unwrap();
+            match store.delete_entry(&id) {
+                Ok(true) => Status::Ok,
+                Ok(false) => Status::NotFound,
+                Err(err) => error_status(&err),
+            }
+        }
+        None => Status::BadRequest,
+    }
+}
+
 #[get("/")]
 fn index() -> (ContentType, String) {
     // TODO: get rid of unwrap
@@ -1,80 +1,647 @@
 use crate::errors::Error;
+use crate::filter::Filter;
 use crate::types::*;
 use crate::{ClockingStore, Result};
 use chrono::prelude::*;
+use log::warn;
+use regex::Regex;
+use rusqlite::functions::FunctionFlags;
 use rusqlite::Connection;
 use rusqlite::OptionalExtension;
 use std::borrow::Cow;
 
 pub(crate) struct SqliteStore {
     conn: Connection,
+    skip_corrupt: bool,
 }
 
 const IN_MEMORY: &str = ":memory:";
+const TAG_SEPARATOR: char = '\u{1f}';
+/// Column `tags` of tag names joined by [`TAG_SEPARATOR`], to be selected along with table `clocking`.
+const TAGS_COLUMN: &str = "(SELECT group_concat(tag.name, char(31)) FROM clocking_tag JOIN tag ON tag.id = clocking_tag.tag_id
+    WHERE clocking_tag.clocking_id = clocking.id) AS tags";
+const PAUSE_SEPARATOR: char = '/';
+/// Column `pauses` of `start/end` pairs joined by [`TAG_SEPARATOR`], `end` is empty for an open pause.
+const PAUSES_COLUMN: &str =
+    "(SELECT group_concat(pause.start || '/' || ifnull(pause.end, ''), char(31)) FROM pause
+    WHERE pause.clocking_id = clocking.id) AS pauses";
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+type EntryParts<'a> = (EntryId<'a>, Cow<'a, str>, Vec<String>, Vec<Pause>);
+/// Ordered schema migrations, `PRAGMA user_version` records how many of them have been applied.
+///
+/// Never modify an released migration, append a new one instead.
+/// The first two are idempotent because databases created before versioning already have their tables.
+const MIGRATIONS: &[&str] = &[
+    // 1: entries
+    "CREATE TABLE IF NOT EXISTS clocking (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        start TEXT NOT NULL,
+        end TEXT NULL,
+        notes TEXT NULL
+     );",
+    // 2: tags
+    "CREATE TABLE IF NOT EXISTS tag (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+     );
+     CREATE TABLE IF NOT EXISTS clocking_tag (
+        clocking_id INTEGER NOT NULL REFERENCES clocking(id) ON DELETE CASCADE,
+        tag_id INTEGER NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+        PRIMARY KEY (clocking_id, tag_id)
+     );",
+    // 3: pauses
+    "CREATE TABLE pause (
+        id INTEGER PRIMARY KEY,
+        clocking_id INTEGER NOT NULL REFERENCES clocking(id) ON DELETE CASCADE,
+        start TEXT NOT NULL,
+        end TEXT NULL
+     );
+     CREATE INDEX pause_clocking_id ON pause(clocking_id);",
+    // 4: per database settings
+    "CREATE TABLE setting (
+        name TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+     );",
+    // 5: billable rates
+    "CREATE TABLE rate (
+        project TEXT PRIMARY KEY,
+        client TEXT NOT NULL,
+        cents_per_hour INTEGER NOT NULL
+     );",
+    // 6: goals, subject is a title or `tag:name`
+    "CREATE TABLE goal (
+        subject TEXT NOT NULL,
+        period TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        minutes INTEGER NOT NULL,
+        PRIMARY KEY (subject, period, kind)
+     );",
+];
+const TRACKING_MODE_SETTING: &str = "tracking_mode";
+/// Maximum entry length in minutes, 0 if disabled.
+const MAX_ENTRY_LENGTH_SETTING: &str = "max_entry_minutes";
+/// Working hours of each weekday, see [`Workday`] for the format.
+const WORKDAY_SETTING: &str = "workday";
+
 impl SqliteStore {
-    pub(crate) fn new(p: &str) -> Self {
-        let conn = if p == IN_MEMORY {
-            Connection::open_in_memory().expect("Should be able to open in memory sqlite.")
+    pub(crate) fn new(p: &str) -> Result<Self> {
+        let mut conn = if p == IN_MEMORY {
+            Connection::open_in_memory()?
         } else {
-            // TODO: logging before panic
-            Connection::open(p).expect("Falied to open sqlite at specified location.")
+            Connection::open(p)?
         };
 
-        // TODO: logging before panic
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS clocking (
-                id INTEGER PRIMARY KEY,
-                title TEXT NOT NULL,
-                start TEXT NOT NULL,
-                end TEXT NULL,
-                notes TEXT NULL
-             )",
-            (),
-        )
-        .expect("Initialize table failed.");
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Self::migrate(&mut conn, MIGRATIONS)?;
+        Self::register_regexp(&conn)?;
 
-        SqliteStore { conn }
+        Ok(SqliteStore {
+            conn,
+            skip_corrupt: false,
+        })
     }
 
-    fn row_to_finished_entry<'a>(row: &'_ rusqlite::Row<'_>) -> FinishedEntry<'a> {
-        let start_string: String = row.get("start").unwrap();
-        let end_string: String = row.get("end").unwrap();
-        FinishedEntry {
-            id: EntryId {
-                title: Cow::Owned(row.get("title").unwrap()),
-                start: DateTime::parse_from_rfc3339(&start_string)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            },
-            end: DateTime::parse_from_rfc3339(&end_string)
-                .unwrap()
-                .with_timezone(&Utc),
-            notes: row
-                .get("notes")
-                .map(Cow::Owned)
-                .unwrap_or_else(|_| Cow::Borrowed("")),
+    /// Apply pending `migrations`, each in its own transaction along with the `user_version` bump.
+    ///
+    /// Fails with [`Error::SchemaTooNew`] if the database was migrated by a newer version.
+    fn migrate(conn: &mut Connection, migrations: &[&str]) -> Result<()> {
+        let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        let supported = migrations.len() as u32;
+        if version > supported {
+            return Err(Error::SchemaTooNew(version, supported));
+        }
+
+        for (i, migration) in migrations.iter().enumerate().skip(version as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i as u32 + 1)?;
+            tx.commit()?;
         }
+        Ok(())
     }
 
-    fn row_to_unfinished_entry<'a>(row: &'_ rusqlite::Row<'_>) -> UnfinishedEntry<'a> {
-        let start_string: String = row.get("start").unwrap();
-        UnfinishedEntry {
-            id: EntryId {
-                title: Cow::Owned(row.get("title").unwrap()),
-                start: DateTime::parse_from_rfc3339(&start_string)
-                    .unwrap()
-                    .with_timezone(&Utc),
+    /// Register `regexp(pattern, text)`, which backs the `REGEXP` operator used by [`Filter`].
+    fn register_regexp(conn: &Connection) -> rusqlite::Result<()> {
+        conn.create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let re = ctx.get_or_create_aux(0, |vr| -> std::result::Result<_, BoxError> {
+                    Ok(Regex::new(vr.as_str()?)?)
+                })?;
+                let text = ctx
+                    .get_raw(1)
+                    .as_str()
+                    .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?;
+                Ok(re.is_match(text))
             },
-            notes: row
-                .get("notes")
-                .map(Cow::Owned)
-                .unwrap_or_else(|_| Cow::Borrowed("")),
+        )
+    }
+
+    /// Look up the row id of an entry, finished or not.
+    fn entry_rowid(conn: &Connection, id: &EntryId) -> Result<Option<i64>> {
+        conn.query_row(
+            "SELECT id FROM clocking WHERE title = ? and start = ?",
+            [id.title.as_ref(), &id.start.to_rfc3339()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.into())
+    }
+
+    fn insert_tags(conn: &Connection, rowid: i64, tags: &[String]) -> Result<()> {
+        for tag in tags.iter() {
+            conn.execute("INSERT OR IGNORE INTO tag (name) VALUES (?)", [tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO clocking_tag (clocking_id, tag_id) SELECT ?, id FROM tag WHERE name = ?",
+                (rowid, tag),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn insert_pauses(conn: &Connection, rowid: i64, pauses: &[Pause]) -> Result<()> {
+        for pause in pauses.iter() {
+            conn.execute(
+                "INSERT INTO pause (clocking_id, start, end) VALUES (?, ?, ?)",
+                (
+                    rowid,
+                    pause.start.to_rfc3339(),
+                    pause.end.map(|end| end.to_rfc3339()),
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn validate_finished(entry: &FinishedEntry) -> Result<()> {
+        if entry.end <= entry.id.start {
+            return Err(Error::InvalidInput("end must be after start"));
+        }
+        crate::store_helper::validate_time(&entry.id.start)?;
+        crate::store_helper::validate_time(&entry.end)?;
+        crate::store_helper::validate_pauses(&entry.pauses, &entry.id.start, &entry.end)?;
+        crate::store_helper::validate_tags(&entry.tags)
+    }
+
+    /// Insert `entry` along with its tags and pauses, fails if the same id exists.
+    fn insert_finished(conn: &Connection, entry: &FinishedEntry) -> Result<()> {
+        if Self::entry_rowid(conn, &entry.id)?.is_some() {
+            return Err(Error::DuplicateEntry);
+        }
+        match conn.execute(
+            "INSERT INTO clocking (title, start, end, notes) VALUES(?, ?, ?, ?)",
+            [
+                entry.id.title.as_ref(),
+                &entry.id.start.to_rfc3339(),
+                &entry.end.to_rfc3339(),
+                entry.notes.as_ref(),
+            ],
+        ) {
+            Ok(1) => {
+                let rowid = conn.last_insert_rowid();
+                Self::insert_tags(conn, rowid, &entry.tags)?;
+                Self::insert_pauses(conn, rowid, &entry.pauses)
+            }
+            Ok(inserted) => Err(Error::ImpossibleState(format!(
+                "abnormal inserted count: {}",
+                inserted
+            ))),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Insert `entry` along with its tags and pauses, without checking duplicated or unfinished entries.
+    fn insert_unfinished(conn: &Connection, entry: &UnfinishedEntry) -> Result<()> {
+        match conn.execute(
+            "INSERT INTO clocking (title, start, notes) VALUES(?, ?, ?)",
+            [
+                entry.id.title.as_ref(),
+                &entry.id.start.to_rfc3339(),
+                entry.notes.as_ref(),
+            ],
+        ) {
+            Ok(1) => {
+                let rowid = conn.last_insert_rowid();
+                Self::insert_tags(conn, rowid, &entry.tags)?;
+                Self::insert_pauses(conn, rowid, &entry.pauses)
+            }
+            Ok(inserted) => Err(Error::ImpossibleState(format!(
+                "abnormal inserted count: {}",
+                inserted
+            ))),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// End the open pause of entry `rowid` at `end`, if there is one.
+    fn close_pause(conn: &Connection, rowid: i64, end: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE pause SET end = ? WHERE clocking_id = ? AND end IS NULL",
+            (end, rowid),
+        )?;
+        Ok(())
+    }
+
+    /// Close open pauses of the entry at `end`, and clip its pauses to `[start, end]`, dropping
+    /// the ones outside.
+    fn clip_pauses(conn: &Connection, rowid: i64, start: &str, end: &str) -> Result<()> {
+        Self::close_pause(conn, rowid, end)?;
+        conn.execute(
+            "DELETE FROM pause WHERE clocking_id = ?1 AND (start >= ?3 OR end <= ?2)",
+            (rowid, start, end),
+        )?;
+        conn.execute(
+            "UPDATE pause SET start = ?2 WHERE clocking_id = ?1 AND start < ?2",
+            (rowid, start),
+        )?;
+        conn.execute(
+            "UPDATE pause SET end = ?2 WHERE clocking_id = ?1 AND end > ?2",
+            (rowid, end),
+        )?;
+        Ok(())
+    }
+
+    /// Pause or resume the unfinished entry of `id` at `at`, or the only unfinished one if `id` is
+    /// None, returns its title.
+    ///
+    /// Returns Ok(None) if there is no such entry, or it's already in the wanted state.
+    fn toggle_pause(
+        &mut self,
+        id: Option<&EntryId>,
+        at: &DateTime<Utc>,
+        pause: bool,
+    ) -> Result<Option<String>> {
+        crate::store_helper::validate_time(at)?;
+        let at_string = at.to_rfc3339();
+        let tx = self.conn.transaction()?;
+        let to_parts = |row: &rusqlite::Row<'_>| Ok((row.get(0)?, row.get(1)?, row.get(2)?));
+        let mut found: Vec<(i64, String, String)> = match id {
+            Some(id) => tx
+                .query_row(
+                    "SELECT id, title, start FROM clocking WHERE end IS NULL AND title = ? AND start = ?",
+                    (&id.title, &id.start.to_rfc3339()),
+                    to_parts,
+                )
+                .optional()?
+                .into_iter()
+                .collect(),
+            None => tx
+                .prepare("SELECT id, title, start FROM clocking WHERE end IS NULL LIMIT 2")?
+                .query_map([], to_parts)?
+                .collect::<rusqlite::Result<_>>()?,
+        };
+        if found.len() > 1 {
+            return Err(Error::InvalidInput(
+                "several entries are unfinished, choose one to pause or resume",
+            ));
+        }
+        let (rowid, title, start) = match found.pop() {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        // (start, end) of the latest pause
+        let last_pause: Option<(String, Option<String>)> = tx
+            .query_row(
+                "SELECT start, end FROM pause WHERE clocking_id = ? ORDER BY start DESC LIMIT 1",
+                [rowid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let paused = matches!(last_pause, Some((_, None)));
+        if paused == pause {
+            return Ok(None);
+        }
+
+        // rfc3339 strings in UTC are ordered as the times they represent
+        let not_before = match last_pause {
+            Some((pause_start, pause_end)) => pause_end.unwrap_or(pause_start),
+            None => start,
+        };
+        if at_string <= not_before {
+            return Err(Error::InvalidInput(
+                "pause and resume time must be after start and previous pauses",
+            ));
+        }
+
+        if pause {
+            tx.execute(
+                "INSERT INTO pause (clocking_id, start) VALUES (?, ?)",
+                (rowid, &at_string),
+            )?;
+        } else {
+            Self::close_pause(&tx, rowid, &at_string)?;
+        }
+        tx.commit()?;
+        Ok(Some(title))
+    }
+
+    fn setting(&self, name: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM setting WHERE name = ?", [name], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(|e| e.into())
+    }
+
+    fn set_setting(&self, name: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO setting (name, value) VALUES (?, ?) ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+            [name, value],
+        )?;
+        Ok(())
+    }
+
+    /// Collect decoded rows, corrupt rows are skipped with a warning if `skip_corrupt` is on.
+    fn collect_rows<T>(
+        &self,
+        rows: impl Iterator<Item = rusqlite::Result<Result<T>>>,
+    ) -> Result<Vec<T>> {
+        let mut r = vec![];
+        for row in rows {
+            match row? {
+                Ok(t) => r.push(t),
+                Err(err) if self.skip_corrupt => warn!("Skipped: {}", err.to_string().trim_end()),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(r)
+    }
+
+    fn row_to_tags(row: &'_ rusqlite::Row<'_>) -> rusqlite::Result<Vec<String>> {
+        let mut tags: Vec<String> = row
+            .get::<_, Option<String>>("tags")?
+            .map(|s| s.split(TAG_SEPARATOR).map(String::from).collect())
+            .unwrap_or_default();
+        tags.sort();
+        Ok(tags)
+    }
+
+    fn row_to_pauses(row: &'_ rusqlite::Row<'_>) -> std::result::Result<Vec<Pause>, String> {
+        let parse = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| format!("pause '{value}': {e}"))
+        };
+        let mut pauses = vec![];
+        let joined: Option<String> = row.get("pauses").map_err(|e| format!("pauses: {e}"))?;
+        for pair in joined.iter().flat_map(|s| s.split(TAG_SEPARATOR)) {
+            let (start, end) = pair
+                .split_once(PAUSE_SEPARATOR)
+                .ok_or_else(|| format!("pause '{pair}'"))?;
+            pauses.push(Pause {
+                start: parse(start)?,
+                end: match end {
+                    "" => None,
+                    end => Some(parse(end)?),
+                },
+            });
+        }
+        pauses.sort_by_key(|p| p.start);
+        Ok(pauses)
+    }
+
+    /// Decode datetime column `column`, or describe why it's corrupt.
+    fn row_to_datetime(
+        row: &'_ rusqlite::Row<'_>,
+        column: &str,
+    ) -> std::result::Result<DateTime<Utc>, String> {
+        let value: String = row.get(column).map_err(|e| format!("{column}: {e}"))?;
+        DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|e| format!("{column} '{value}': {e}"))
+    }
+
+    /// Decode columns shared by finished and unfinished entries, as `(id, notes, tags, pauses)`.
+    fn row_to_entry_parts<'a>(
+        row: &'_ rusqlite::Row<'_>,
+    ) -> std::result::Result<EntryParts<'a>, String> {
+        let id = EntryId {
+            title: Cow::Owned(row.get("title").map_err(|e| format!("title: {e}"))?),
+            start: Self::row_to_datetime(row, "start")?,
+        };
+        let notes = row
+            .get::<_, Option<String>>("notes")
+            .map_err(|e| format!("notes: {e}"))?
+            .map_or(Cow::Borrowed(""), Cow::Owned);
+        let tags = Self::row_to_tags(row).map_err(|e| format!("tags: {e}"))?;
+        let pauses = Self::row_to_pauses(row)?;
+        Ok((id, notes, tags, pauses))
+    }
+
+    /// Describe a corrupt row by its id.
+    fn corrupt_row(row: &'_ rusqlite::Row<'_>, reason: String) -> Error {
+        match row.get::<_, i64>("id") {
+            Ok(id) => Error::ImpossibleState(format!("corrupt row {id}: {reason}")),
+            Err(_) => Error::ImpossibleState(format!("corrupt row: {reason}")),
         }
     }
+
+    fn row_to_finished_entry<'a>(row: &'_ rusqlite::Row<'_>) -> Result<FinishedEntry<'a>> {
+        Self::row_to_entry_parts(row)
+            .and_then(|(id, notes, tags, pauses)| {
+                Ok(FinishedEntry {
+                    id,
+                    end: Self::row_to_datetime(row, "end")?,
+                    notes,
+                    tags,
+                    pauses,
+                })
+            })
+            .map_err(|reason| Self::corrupt_row(row, reason))
+    }
+
+    fn row_to_unfinished_entry<'a>(row: &'_ rusqlite::Row<'_>) -> Result<UnfinishedEntry<'a>> {
+        Self::row_to_entry_parts(row)
+            .map(|(id, notes, tags, pauses)| UnfinishedEntry {
+                id,
+                notes,
+                tags,
+                pauses,
+            })
+            .map_err(|reason| Self::corrupt_row(row, reason))
+    }
+}
+
+/// SQLite integers are signed, `usize::MAX` means no limit.
+fn sql_limit(limit: usize) -> i64 {
+    i64::try_from(limit).unwrap_or(i64::MAX)
 }
 
 impl ClockingStore for SqliteStore {
+    fn tracking_mode(&self) -> Result<TrackingMode> {
+        match self.setting(TRACKING_MODE_SETTING)? {
+            Some(value) => value.parse().map_err(|_| {
+                Error::ImpossibleState(format!("corrupt setting {TRACKING_MODE_SETTING}: {value}"))
+            }),
+            None => Ok(TrackingMode::default()),
+        }
+    }
+
+    fn set_tracking_mode(&mut self, mode: TrackingMode) -> Result<()> {
+        if mode == TrackingMode::Single {
+            let unfinished = self.unfinished(usize::MAX)?;
+            if unfinished.len() > 1 {
+                let titles: Vec<&str> = unfinished.iter().map(|e| e.id.title.as_ref()).collect();
+                return Err(Error::UnfinishedExists(titles.join(", ")));
+            }
+        }
+        self.set_setting(TRACKING_MODE_SETTING, &mode.to_string())
+    }
+
+    fn max_entry_length(&self) -> Result<Option<chrono::Duration>> {
+        match self.setting(MAX_ENTRY_LENGTH_SETTING)? {
+            Some(value) => match value.parse::<i64>() {
+                Ok(0) => Ok(None),
+                Ok(minutes) => Ok(Some(chrono::Duration::minutes(minutes))),
+                Err(_) => Err(Error::ImpossibleState(format!(
+                    "corrupt setting {MAX_ENTRY_LENGTH_SETTING}: {value}"
+                ))),
+            },
+            None => Ok(Some(chrono::Duration::hours(
+                crate::DEFAULT_MAX_ENTRY_LENGTH_HOURS,
+            ))),
+        }
+    }
+
+    fn set_max_entry_length(&mut self, length: Option<chrono::Duration>) -> Result<()> {
+        let minutes = match length {
+            Some(length) if length.num_minutes() <= 0 => {
+                return Err(Error::InvalidInput(
+                    "maximum entry length must be at least one minute",
+                ))
+            }
+            // overdue entries are those started `length` before now
+            Some(length) if Utc::now().checked_sub_signed(length).is_none() => {
+                return Err(Error::InvalidInput("maximum entry length is too long"))
+            }
+            Some(length) => length.num_minutes(),
+            None => 0,
+        };
+        self.set_setting(MAX_ENTRY_LENGTH_SETTING, &minutes.to_string())
+    }
+
+    fn workday(&self) -> Result<Workday> {
+        match self.setting(WORKDAY_SETTING)? {
+            Some(value) => value.parse().map_err(|_| {
+                Error::ImpossibleState(format!("corrupt setting {WORKDAY_SETTING}: {value}"))
+            }),
+            None => Ok(Workday::default()),
+        }
+    }
+
+    fn set_workday(&mut self, workday: &Workday) -> Result<()> {
+        self.set_setting(WORKDAY_SETTING, &workday.to_string())
+    }
+
+    fn rates(&self) -> Result<Vec<Rate>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT project, client, cents_per_hour FROM rate ORDER BY project")?;
+        let rates = stmt
+            .query_map([], |row| {
+                Ok(Rate {
+                    project: row.get(0)?,
+                    client: row.get(1)?,
+                    cents_per_hour: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(rates)
+    }
+
+    fn set_rate(&mut self, rate: &Rate) -> Result<()> {
+        let project = project_path(&rate.project).collect::<Vec<_>>().join("/");
+        if project.is_empty() {
+            return Err(Error::InvalidInput("project must not be empty"));
+        }
+        if rate.client.trim().is_empty() {
+            return Err(Error::InvalidInput("client must not be empty"));
+        }
+        if rate.cents_per_hour < 0 {
+            return Err(Error::InvalidInput("rate must not be negative"));
+        }
+        self.conn.execute(
+            "INSERT INTO rate (project, client, cents_per_hour) VALUES (?, ?, ?)
+             ON CONFLICT (project) DO UPDATE SET client = excluded.client, cents_per_hour = excluded.cents_per_hour",
+            rusqlite::params![project, rate.client.trim(), rate.cents_per_hour],
+        )?;
+        Ok(())
+    }
+
+    fn remove_rate(&mut self, project: &str) -> Result<bool> {
+        let project = project_path(project).collect::<Vec<_>>().join("/");
+        let count = self
+            .conn
+            .execute("DELETE FROM rate WHERE project = ?", [project])?;
+        Ok(count > 0)
+    }
+
+    fn goals(&self) -> Result<Vec<Goal>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT subject, period, kind, minutes FROM goal")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut goals = rows
+            .into_iter()
+            .map(|(subject, period, kind, minutes)| {
+                let corrupt = || Error::ImpossibleState(format!("corrupt goal of {subject}"));
+                Ok(Goal {
+                    subject: subject.parse().map_err(|_| corrupt())?,
+                    period: period.parse().map_err(|_| corrupt())?,
+                    kind: kind.parse().map_err(|_| corrupt())?,
+                    duration: chrono::Duration::minutes(minutes),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        goals.sort_by(|a, b| (&a.subject, a.period, a.kind).cmp(&(&b.subject, b.period, b.kind)));
+        Ok(goals)
+    }
+
+    fn set_goal(&mut self, goal: &Goal) -> Result<()> {
+        if goal.duration.num_minutes() <= 0 {
+            return Err(Error::InvalidInput("goal must be at least one minute"));
+        }
+        self.conn.execute(
+            "INSERT INTO goal (subject, period, kind, minutes) VALUES (?, ?, ?, ?)
+             ON CONFLICT (subject, period, kind) DO UPDATE SET minutes = excluded.minutes",
+            rusqlite::params![
+                goal.subject.to_string(),
+                goal.period.to_string(),
+                goal.kind.to_string(),
+                goal.duration.num_minutes()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_goal(
+        &mut self,
+        subject: &GoalSubject,
+        period: GoalPeriod,
+        kind: GoalKind,
+    ) -> Result<bool> {
+        let count = self.conn.execute(
+            "DELETE FROM goal WHERE subject = ? AND period = ? AND kind = ?",
+            [subject.to_string(), period.to_string(), kind.to_string()],
+        )?;
+        Ok(count > 0)
+    }
+
     fn start_entry(&mut self, entry: &UnfinishedEntry) -> Result<()> {
+        crate::store_helper::validate_time(&entry.id.start)?;
         let start_time_string = entry.id.start.to_rfc3339();
         // check exists
         self.conn
@@ -93,153 +660,455 @@ impl ClockingStore for SqliteStore {
             })?;
 
         // check unfinished
-        self.conn
+        if self.tracking_mode()? == TrackingMode::Single {
+            self.conn
+                .query_row(
+                    "SELECT title FROM clocking WHERE end is null limit 1",
+                    [],
+                    |row| row.get("title").map(Some),
+                )
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    err => Err(err.into()),
+                })
+                .and_then(|r| match r {
+                    Some(t) => Err(Error::UnfinishedExists(t)),
+                    None => Ok(()),
+                })?;
+        }
+
+        crate::store_helper::validate_tags(&entry.tags)?;
+
+        // insert
+        let tx = self.conn.transaction()?;
+        Self::insert_unfinished(&tx, entry)?;
+        tx.commit().map_err(|e| e.into())
+    }
+
+    fn switch_to_entry(&mut self, entry: &UnfinishedEntry, notes: &str) -> Result<Option<String>> {
+        crate::store_helper::validate_tags(&entry.tags)?;
+        crate::store_helper::validate_time(&entry.id.start)?;
+        let at_string = entry.id.start.to_rfc3339();
+
+        let tx = self.conn.transaction()?;
+        let ongoing: Option<(i64, String, String)> = tx
             .query_row(
-                "SELECT title FROM clocking WHERE end is null limit 1",
+                "SELECT id, title, start FROM clocking WHERE end IS NULL ORDER BY start DESC LIMIT 1",
                 [],
-                |row| Ok(Some(row.get("title").unwrap())),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
-            .or_else(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => Ok(None),
-                err => Err(err.into()),
-            })
-            .and_then(|r| match r {
-                Some(t) => Err(Error::UnfinishedExists(t)),
-                None => Ok(()),
-            })?;
+            .optional()?;
+        let finished = match ongoing {
+            Some((rowid, title, start)) => {
+                if start >= at_string {
+                    return Err(Error::InvalidInput(
+                        "the new entry must start after the ongoing one",
+                    ));
+                }
+                tx.execute(
+                    "UPDATE clocking SET end = ?, notes = IFNULL(notes, '')||? WHERE id = ?",
+                    (&at_string, notes, rowid),
+                )?;
+                Self::close_pause(&tx, rowid, &at_string)?;
+                Some(title)
+            }
+            None => None,
+        };
 
-        // insert
-        match self.conn.execute(
-            "INSERT INTO clocking (title, start, notes) VALUES(?, ?, ?)",
-            [
-                entry.id.title.as_ref(),
-                &start_time_string,
-                entry.notes.as_ref(),
-            ],
-        ) {
-            Ok(1) => Ok(()),
-            Ok(inserted) => Err(Error::ImpossibleState(format!(
-                "abnormal inserted count: {}",
-                inserted
-            ))),
-            Err(err) => Err(err.into()),
+        if Self::entry_rowid(&tx, &entry.id)?.is_some() {
+            return Err(Error::DuplicateEntry);
+        }
+        Self::insert_unfinished(&tx, entry)?;
+        tx.commit()?;
+        Ok(finished)
+    }
+
+    fn add_finished_entry(&mut self, entry: &FinishedEntry) -> Result<()> {
+        Self::validate_finished(entry)?;
+        let tx = self.conn.transaction()?;
+        Self::insert_finished(&tx, entry)?;
+        tx.commit().map_err(|e| e.into())
+    }
+
+    fn import_finished<'a>(
+        &mut self,
+        entries: &[FinishedEntry<'a>],
+        dry_run: bool,
+    ) -> Result<ImportReport<'a>> {
+        let tx = self.conn.transaction()?;
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        for entry in entries.iter() {
+            Self::validate_finished(entry)?;
+            match Self::insert_finished(&tx, entry) {
+                Ok(()) => report.imported += 1,
+                Err(Error::DuplicateEntry) => report.duplicates.push(entry.id.clone()),
+                Err(err) => return Err(err),
+            }
+        }
+        if !dry_run {
+            tx.commit()?;
         }
+        Ok(report)
     }
 
     fn try_finish_any(&mut self, notes: &str) -> Result<Option<String>> {
         let end_string = Utc::now().to_rfc3339();
-        self.conn
+        let tx = self.conn.transaction()?;
+        let finished: Option<(i64, String)> = tx
             .query_row(
                 "UPDATE clocking set end = ?, notes = IFNULL(notes, '')||? where id in (
-                    SELECT max(id) FROM clocking WHERE end is NULL
-            ) returning title",
+                    SELECT id FROM clocking WHERE end is NULL ORDER BY start DESC LIMIT 1
+            ) returning id, title",
                 [&end_string, notes],
-                |row| Ok(Some(row.get("title").unwrap())),
+                |row| Ok((row.get("id")?, row.get("title")?)),
             )
-            .or_else(|e| match e {
-                rusqlite::Error::QueryReturnedNoRows => Ok(None),
-                other => Err(other.into()),
-            })
+            .optional()?;
+        match finished {
+            Some((rowid, title)) => {
+                Self::close_pause(&tx, rowid, &end_string)?;
+                tx.commit()?;
+                Ok(Some(title))
+            }
+            None => Ok(None),
+        }
     }
 
     fn try_finish_entry(&mut self, id: &EntryId, end: &DateTime<Utc>, notes: &str) -> Result<bool> {
+        crate::store_helper::validate_time(end)?;
         let start_string = id.start.to_rfc3339();
         let end_string = end.to_rfc3339();
-        match self.conn.execute("UPDATE clocking SET end = ?, notes = IFNULL(notes, '')||?  WHERE title = ? and start = ? and end IS NULL and start < ?",
-                           [&end_string, notes, &id.title, &start_string, &end_string]) {
-            Ok(1) => Ok(true),
-            Ok(0) => Ok(false),
-            Ok(updated) => Err(Error::ImpossibleState(format!("abnormal updated count: {}", updated))),
-            Err(err) => Err(err.into()),
+        let tx = self.conn.transaction()?;
+        let finished: Option<i64> = tx
+            .query_row("UPDATE clocking SET end = ?, notes = IFNULL(notes, '')||?  WHERE title = ? and start = ? and end IS NULL and start < ? returning id",
+                       [&end_string, notes, &id.title, &start_string, &end_string],
+                       |row| row.get("id"))
+            .optional()?;
+        match finished {
+            Some(rowid) => {
+                Self::close_pause(&tx, rowid, &end_string)?;
+                tx.commit()?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
+    fn try_pause_any(&mut self, at: &DateTime<Utc>) -> Result<Option<String>> {
+        self.toggle_pause(None, at, true)
+    }
+
+    fn try_resume_any(&mut self, at: &DateTime<Utc>) -> Result<Option<String>> {
+        self.toggle_pause(None, at, false)
+    }
+
+    fn try_pause_entry(&mut self, id: &EntryId, at: &DateTime<Utc>) -> Result<bool> {
+        Ok(self.toggle_pause(Some(id), at, true)?.is_some())
+    }
+
+    fn try_resume_entry(&mut self, id: &EntryId, at: &DateTime<Utc>) -> Result<bool> {
+        Ok(self.toggle_pause(Some(id), at, false)?.is_some())
+    }
+
     fn finished<'a>(
         &self,
         query_start: &DateTime<Utc>,
         query_end: Option<DateTime<Utc>>,
+        filter: Option<&Filter>,
     ) -> Result<Vec<FinishedEntry<'a>>> {
         let start_string = query_start.to_rfc3339();
         let end_string = query_end.map_or_else(|| Utc::now().to_rfc3339(), |x| x.to_rfc3339());
-        let mut stmt = self.conn.prepare(
-            "SELECT title, start, end, notes from clocking where start >= ? and end is not null and end <= ? order by start ")?;
-        let r = stmt.query_map([&start_string, &end_string], |row| {
+        let mut params = vec![end_string, start_string];
+        let filter_sql = filter.map_or_else(|| "1".to_string(), |f| f.to_sql(&mut params));
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where end is not null and start < ? and end > ? and {filter_sql} order by start "))?;
+        let r = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
             Ok(SqliteStore::row_to_finished_entry(row))
         })?;
-        Ok(r.map(|x| x.unwrap()).collect())
+        self.collect_rows(r)
     }
 
-    fn latest_finished(&self, title: &str) -> Result<Option<FinishedEntry>> {
+    fn latest_finished<'a>(&self, title: &str) -> Result<Option<FinishedEntry<'a>>> {
         self.conn.query_row(
-            "SELECT title, start, end, notes from clocking where title = ? and end is not null order by start desc limit 1",
+            &format!("SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where title = ? and end is not null order by start desc limit 1"),
             [title],
             |row| Ok(SqliteStore::row_to_finished_entry(row)))
-            .optional()
-            .map_err(|e| e.into())
+            .optional()?
+            .transpose()
     }
 
     fn recent_titles(&self, limit: usize) -> Result<Vec<String>> {
         let mut stmt = self
             .conn
             .prepare("SELECT title, max(start) FROM clocking where end is not null group by title order by max(start) desc limit ?")?;
-        let r = stmt.query_map([limit], |row| Ok(row.get("title").unwrap()))?;
+        let r = stmt.query_map([sql_limit(limit)], |row| row.get("title"))?;
 
-        Ok(r.map(|x| x.unwrap()).collect())
+        r.collect::<rusqlite::Result<_>>().map_err(|e| e.into())
     }
 
     fn unfinished<'a>(&self, limit: usize) -> Result<Vec<UnfinishedEntry<'a>>> {
         let mut stmt = self
             .conn
             .prepare(
-                "select title, start, notes from clocking where end is null order by start desc limit ?",
+                &format!("select id, title, start, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where end is null order by start desc limit ?"),
             )?;
-        let r = stmt.query_map([limit], |row| Ok(SqliteStore::row_to_unfinished_entry(row)))?;
-        Ok(r.map(|x| x.unwrap()).collect())
+        let r = stmt.query_map([sql_limit(limit)], |row| {
+            Ok(SqliteStore::row_to_unfinished_entry(row))
+        })?;
+        self.collect_rows(r)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn finished_overlapping<'a>(
+        &self,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<FinishedEntry<'a>>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where end is not null and start < ? and end > ? order by start"))?;
+        let r = stmt.query_map([&end.to_rfc3339(), &start.to_rfc3339()], |row| {
+            Ok(SqliteStore::row_to_finished_entry(row))
+        })?;
+        self.collect_rows(r)
+    }
 
-    #[test]
-    fn sqlite_store_basic_workflow() {
-        let mut mem_store = SqliteStore::new(IN_MEMORY);
-        let start_time = Utc::now();
-        let entry = UnfinishedEntry {
-            id: EntryId {
-                title: "The Program".into(),
-                start: start_time,
-            },
-            notes: "".into(),
-        };
+    fn finished_covering<'a>(&self, at: &DateTime<Utc>) -> Result<Vec<FinishedEntry<'a>>> {
+        let at_string = at.to_rfc3339();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where end is not null and start <= ? and end >= ? order by start"))?;
+        let r = stmt.query_map([&at_string, &at_string], |row| {
+            Ok(SqliteStore::row_to_finished_entry(row))
+        })?;
+        self.collect_rows(r)
+    }
 
-        assert!(mem_store.start_entry(&entry).is_ok());
-        // add again
-        assert_eq!(
-            mem_store.start_entry(&entry),
-            Err(Error::DuplicateEntry),
-            "Adding the same item twice should fail."
-        );
+    fn update_entry(&mut self, id: &EntryId, updated: &FinishedEntry) -> Result<bool> {
+        if updated.end <= updated.id.start {
+            return Err(Error::InvalidInput("end must be after start"));
+        }
+        crate::store_helper::validate_time(&updated.id.start)?;
+        crate::store_helper::validate_time(&updated.end)?;
 
-        let finished_entries = mem_store.finished(&start_time, None);
-        assert_eq!(
-            finished_entries.unwrap().len(),
-            0,
-            "Unfinished entries should not included in query."
-        );
+        let tx = self.conn.transaction()?;
+        let rowid = match Self::entry_rowid(&tx, id)? {
+            Some(rowid) => rowid,
+            None => return Ok(false),
+        };
+        match Self::entry_rowid(&tx, &updated.id)? {
+            Some(other) if other != rowid => return Err(Error::DuplicateEntry),
+            _ => {}
+        }
 
-        let end = Utc::now();
-        let note = "A note";
-        assert_eq!(mem_store.try_finish_entry(&entry.id, &end, note), Ok(true));
-        //finish again
-        assert_eq!(
-            mem_store.try_finish_entry(&entry.id, &end, note),
-            Ok(false),
+        let start_string = updated.id.start.to_rfc3339();
+        let end_string = updated.end.to_rfc3339();
+        match tx.execute(
+            "UPDATE clocking SET title = ?, start = ?, end = ?, notes = ? WHERE id = ?",
+            (
+                updated.id.title.as_ref(),
+                &start_string,
+                &end_string,
+                updated.notes.as_ref(),
+                rowid,
+            ),
+        ) {
+            Ok(1) => {
+                Self::clip_pauses(&tx, rowid, &start_string, &end_string)?;
+                tx.commit()?;
+                Ok(true)
+            }
+            Ok(n) => Err(Error::ImpossibleState(format!(
+                "abnormal updated count: {n}"
+            ))),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn split_entry(
+        &mut self,
+        id: &EntryId,
+        gap_start: &DateTime<Utc>,
+        gap_end: &DateTime<Utc>,
+    ) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        let found = tx
+            .query_row(
+                &format!("SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking where title = ? and start = ? and end is not null"),
+                [id.title.as_ref(), &id.start.to_rfc3339()],
+                |row| Ok((row.get::<_, i64>("id")?, SqliteStore::row_to_finished_entry(row))),
+            )
+            .optional()?;
+        let (rowid, entry) = match found {
+            Some((rowid, entry)) => (rowid, entry?),
+            None => return Ok(false),
+        };
+        if !(entry.id.start < *gap_start && gap_start <= gap_end && *gap_end < entry.end) {
+            return Err(Error::InvalidInput(
+                "gap must be within the entry, leaving both parts non-empty",
+            ));
+        }
+
+        let start_string = entry.id.start.to_rfc3339();
+        let gap_start_string = gap_start.to_rfc3339();
+        tx.execute(
+            "UPDATE clocking SET end = ? WHERE id = ?",
+            (&gap_start_string, rowid),
+        )?;
+        Self::clip_pauses(&tx, rowid, &start_string, &gap_start_string)?;
+        let end = entry.end;
+        for after in crate::store_helper::clip(vec![entry], gap_end, &end) {
+            Self::insert_finished(&tx, &after)?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn delete_entry(&mut self, id: &EntryId) -> Result<bool> {
+        match self.conn.execute(
+            "DELETE FROM clocking WHERE title = ? and start = ?",
+            [id.title.as_ref(), &id.start.to_rfc3339()],
+        ) {
+            Ok(0) => Ok(false),
+            Ok(1) => Ok(true),
+            Ok(n) => Err(Error::ImpossibleState(format!(
+                "abnormal deleted count: {n}"
+            ))),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn add_tags(&mut self, id: &EntryId, tags: &[String]) -> Result<bool> {
+        crate::store_helper::validate_tags(tags)?;
+        let tx = self.conn.transaction()?;
+        match Self::entry_rowid(&tx, id)? {
+            Some(rowid) => {
+                Self::insert_tags(&tx, rowid, tags)?;
+                tx.commit()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn remove_tags(&mut self, id: &EntryId, tags: &[String]) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        match Self::entry_rowid(&tx, id)? {
+            Some(rowid) => {
+                for tag in tags.iter() {
+                    tx.execute(
+                        "DELETE FROM clocking_tag WHERE clocking_id = ? and tag_id IN (SELECT id FROM tag WHERE name = ?)",
+                        (rowid, tag),
+                    )?;
+                }
+                tx.commit()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn tags(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT tag.name FROM tag JOIN clocking_tag ON tag.id = clocking_tag.tag_id order by tag.name",
+        )?;
+        let r = stmt.query_map([], |row| row.get(0))?;
+        r.collect::<rusqlite::Result<_>>().map_err(|e| e.into())
+    }
+
+    fn integrity_report<'a>(&self, stale_after: chrono::Duration) -> Result<IntegrityReport<'a>> {
+        let mut report = IntegrityReport::default();
+        let mut finished: Vec<FinishedEntry> = vec![];
+        let stale_before = Utc::now() - stale_after;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, title, start, end, notes, {TAGS_COLUMN}, {PAUSES_COLUMN} from clocking order by start"
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let unfinished = row.get_ref("end")?.data_type() == rusqlite::types::Type::Null;
+            let decoded = if unfinished {
+                Self::row_to_unfinished_entry(row).map(|entry| {
+                    if entry.id.start < stale_before {
+                        report.stale_unfinished.push(entry);
+                    }
+                })
+            } else {
+                Self::row_to_finished_entry(row).map(|entry| {
+                    if entry.end <= entry.id.start {
+                        report.non_positive.push(entry);
+                    } else {
+                        let (start, end) = (&entry.id.start, &entry.end);
+                        if crate::store_helper::validate_pauses(&entry.pauses, start, end).is_err()
+                        {
+                            report.invalid_pauses.push(entry.clone());
+                        }
+                        finished.push(entry);
+                    }
+                })
+            };
+            if let Err(err) = decoded {
+                report.corrupt.push(err.to_string().trim_end().to_string());
+            }
+        }
+
+        // corrupt starts may break sql ordering
+        finished.sort_by_key(|e| e.id.start);
+        report.overlaps = crate::store_helper::find_overlaps(&finished)
+            .into_iter()
+            .map(|(i, j)| (finished[i].clone(), finished[j].clone()))
+            .collect();
+        Ok(report)
+    }
+
+    fn skip_corrupt(&mut self, skip: bool) {
+        self.skip_corrupt = skip;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlite_store_basic_workflow() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let start_time = Utc::now();
+        let entry = UnfinishedEntry {
+            id: EntryId {
+                title: "The Program".into(),
+                start: start_time,
+            },
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+
+        assert!(mem_store.start_entry(&entry).is_ok());
+        // add again
+        assert_eq!(
+            mem_store.start_entry(&entry),
+            Err(Error::DuplicateEntry),
+            "Adding the same item twice should fail."
+        );
+
+        let finished_entries = mem_store.finished(&start_time, None, None);
+        assert_eq!(
+            finished_entries.unwrap().len(),
+            0,
+            "Unfinished entries should not included in query."
+        );
+
+        let end = Utc::now();
+        let note = "A note";
+        assert_eq!(mem_store.try_finish_entry(&entry.id, &end, note), Ok(true));
+        //finish again
+        assert_eq!(
+            mem_store.try_finish_entry(&entry.id, &end, note),
+            Ok(false),
             "call try_finish_entry on finished entry should fail"
         );
 
-        let finished_entries = mem_store.finished(&start_time, None);
+        let finished_entries = mem_store.finished(&start_time, None, None);
         assert!(finished_entries.is_ok());
         let finished_entries = finished_entries.unwrap();
         assert_eq!(finished_entries.len(), 1);
@@ -248,6 +1117,8 @@ mod tests {
             id: entry.id,
             end,
             notes: note.into(),
+            tags: vec![],
+            pauses: vec![],
         };
 
         assert_eq!(&finished_entries[0], &finished_entry);
@@ -255,7 +1126,7 @@ mod tests {
 
     #[test]
     fn start_while_unfinished_exists() {
-        let mut mem_store = SqliteStore::new(IN_MEMORY);
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
         // item0
         let entries = gen_entries(1);
         assert!(mem_store.start_entry(&entries[0]).is_ok());
@@ -267,6 +1138,8 @@ mod tests {
                 start: Utc::now(),
             },
             notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
         };
 
         let exist_title = entries[0].id.title.to_string();
@@ -276,6 +1149,942 @@ mod tests {
         );
     }
 
+    #[test]
+    fn finished_with_filter() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let query_start = Utc::now() - chrono::Duration::days(1);
+        let titles = [
+            "clocking: server",
+            "clocking: views",
+            "reading",
+            "Clocking plan",
+        ];
+        for title in titles {
+            let start = Utc::now() - chrono::Duration::minutes(10);
+            let entry = UnfinishedEntry {
+                id: EntryId {
+                    title: title.into(),
+                    start,
+                },
+                notes: "".into(),
+                tags: vec![],
+                pauses: vec![],
+            };
+            mem_store.start_entry(&entry).unwrap();
+            assert_eq!(mem_store.try_finish_any(""), Ok(Some(title.to_string())));
+        }
+
+        for (expr, expected) in [
+            ("clocking", vec!["clocking: server", "clocking: views"]),
+            (
+                "[cC]lock*",
+                vec!["clocking: server", "clocking: views", "Clocking plan"],
+            ),
+            (
+                "/^(?i)clocking/ and not views",
+                vec!["clocking: server", "Clocking plan"],
+            ),
+            ("reading or \"g: v\"", vec!["clocking: views", "reading"]),
+        ] {
+            let filter: Filter = expr.parse().unwrap();
+            let mut found: Vec<String> = mem_store
+                .finished(&query_start, None, Some(&filter))
+                .unwrap()
+                .into_iter()
+                .map(|e| e.id.title.to_string())
+                .collect();
+            found.sort();
+            let mut expected: Vec<String> = expected.into_iter().map(String::from).collect();
+            expected.sort();
+            assert_eq!(found, expected, "filter: {expr}");
+            assert!(found.iter().all(|t| filter.matches(t, &[])));
+        }
+    }
+
+    #[test]
+    fn finished_across_range_boundary() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let today = Local::now().date_naive();
+        let at = |days_ago: u64, hour: u32| {
+            let date = today - chrono::Days::new(days_ago);
+            crate::store_helper::local_to_utc(&date.and_hms_opt(hour, 0, 0).unwrap(), &Local)
+        };
+        let entry = FinishedEntry {
+            id: EntryId {
+                title: "Late night".into(),
+                start: at(3, 22),
+            },
+            end: at(2, 2),
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        mem_store.add_finished_entry(&entry).unwrap();
+        let date = |days_ago: u64| {
+            (today - chrono::Days::new(days_ago))
+                .format(crate::NAIVE_DATE_FORMAT)
+                .to_string()
+        };
+        let span = |entries: Vec<FinishedEntry>| {
+            entries
+                .into_iter()
+                .map(|e| (e.id.start, e.end))
+                .collect::<Vec<_>>()
+        };
+
+        let day_before = mem_store.finished_by_date_str(&date(3), &date(3), None);
+        assert_eq!(span(day_before.unwrap()), vec![(at(3, 22), at(2, 0))]);
+        let day_after = mem_store.finished_by_date_str(&date(2), &date(2), None);
+        assert_eq!(span(day_after.unwrap()), vec![(at(2, 0), at(2, 2))]);
+        let by_offset = mem_store.finished_by_offset(2, Some(1), None);
+        assert_eq!(span(by_offset.unwrap()), vec![(at(2, 0), at(2, 2))]);
+        assert_eq!(
+            mem_store.finished_by_date_str(&date(4), &date(4), None),
+            Ok(vec![])
+        );
+        assert_eq!(
+            mem_store.finished_overlapping_dates(&date(2), &date(2), None),
+            Ok(vec![entry])
+        );
+    }
+
+    #[test]
+    fn tags_workflow() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let query_start = Utc::now() - chrono::Duration::days(1);
+        let mut entry = gen_entries(1).pop().unwrap();
+        entry.tags = vec!["work".into(), "meeting".into()];
+        assert!(mem_store.start_entry(&entry).is_ok());
+        assert_eq!(
+            mem_store.unfinished(1).unwrap()[0].tags,
+            vec!["meeting".to_string(), "work".to_string()]
+        );
+
+        let tags = |v: &[&str]| v.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            mem_store.add_tags(&entry.id, &tags(&["work", "zoom"])),
+            Ok(true)
+        );
+        assert_eq!(
+            mem_store.remove_tags(&entry.id, &tags(&["meeting"])),
+            Ok(true)
+        );
+        assert_eq!(
+            mem_store.add_tags(&entry.id, &tags(&["bad tag"])),
+            Err(Error::InvalidInput(
+                "tag must be non-empty without whitespace, parentheses or quotes"
+            ))
+        );
+        for bad in ["(work)", "\"work", "work\u{7}"] {
+            assert!(mem_store.add_tags(&entry.id, &tags(&[bad])).is_err());
+        }
+        mem_store.try_finish_any("").unwrap();
+        assert_eq!(mem_store.tags(), Ok(tags(&["work", "zoom"])));
+
+        let finished = mem_store.finished(&query_start, None, None).unwrap();
+        assert_eq!(finished[0].tags, tags(&["work", "zoom"]));
+
+        for (expr, count) in [("tag:zoom", 1), ("tag:meeting", 0), ("not tag:work", 0)] {
+            let filter: Filter = expr.parse().unwrap();
+            let found = mem_store
+                .finished(&query_start, None, Some(&filter))
+                .unwrap();
+            assert_eq!(found.len(), count, "filter: {expr}");
+        }
+
+        let missing = EntryId {
+            title: "Missing".into(),
+            start: Utc::now(),
+        };
+        assert_eq!(mem_store.add_tags(&missing, &tags(&["work"])), Ok(false));
+    }
+
+    #[test]
+    fn rates_workflow() {
+        let mut store = SqliteStore::new(IN_MEMORY).unwrap();
+        let rate = |project: &str, cents_per_hour| Rate {
+            project: project.to_string(),
+            client: "Acme".to_string(),
+            cents_per_hour,
+        };
+        assert_eq!(store.set_rate(&rate(" acme / website ", 12000)), Ok(()));
+        assert_eq!(store.set_rate(&rate("acme", 10000)), Ok(()));
+        assert_eq!(store.set_rate(&rate("acme/website", 15000)), Ok(()));
+        assert_eq!(
+            store.rates(),
+            Ok(vec![rate("acme", 10000), rate("acme/website", 15000)])
+        );
+        assert_eq!(
+            store.set_rate(&rate(" / ", 100)),
+            Err(Error::InvalidInput("project must not be empty"))
+        );
+        assert_eq!(
+            store.set_rate(&rate("acme", -1)),
+            Err(Error::InvalidInput("rate must not be negative"))
+        );
+
+        let rates = store.rates().unwrap();
+        let found = |title| Rate::find(&rates, title).map(|r| r.cents_per_hour);
+        assert_eq!(found("acme/website/bugs"), Some(15000));
+        assert_eq!(found("acme / support"), Some(10000));
+        assert_eq!(found("acme-corp"), None);
+
+        assert_eq!(store.remove_rate("acme/ website"), Ok(true));
+        assert_eq!(store.remove_rate("acme/website"), Ok(false));
+        assert_eq!(store.rates(), Ok(vec![rate("acme", 10000)]));
+    }
+
+    #[test]
+    fn goals_workflow() {
+        let mut store = SqliteStore::new(IN_MEMORY).unwrap();
+        let goal = Goal::of_hours;
+        let meetings = goal("tag:meeting", GoalPeriod::Week, GoalKind::Cap, 10);
+        let reading = goal(" reading / ", GoalPeriod::Week, GoalKind::Target, 8);
+        assert_eq!(store.set_goal(&meetings), Ok(()));
+        assert_eq!(store.set_goal(&reading), Ok(()));
+        assert_eq!(
+            store.set_goal(&goal("reading", GoalPeriod::Week, GoalKind::Target, 6)),
+            Ok(())
+        );
+        assert_eq!(
+            store.set_goal(&goal("reading", GoalPeriod::Day, GoalKind::Target, 0)),
+            Err(Error::InvalidInput("goal must be at least one minute"))
+        );
+        assert_eq!(
+            store.goals(),
+            Ok(vec![
+                goal("reading", GoalPeriod::Week, GoalKind::Target, 6),
+                meetings.clone()
+            ])
+        );
+
+        assert_eq!(
+            store.remove_goal(&meetings.subject, GoalPeriod::Day, GoalKind::Cap),
+            Ok(false)
+        );
+        assert_eq!(
+            store.remove_goal(&meetings.subject, GoalPeriod::Week, GoalKind::Cap),
+            Ok(true)
+        );
+        assert_eq!(store.goals().unwrap().len(), 1);
+    }
+
+    fn user_version(conn: &Connection) -> u32 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_new_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(SqliteStore::migrate(&mut conn, MIGRATIONS), Ok(()));
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as u32);
+        // migrate again is a no-op
+        assert_eq!(SqliteStore::migrate(&mut conn, MIGRATIONS), Ok(()));
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn migrate_unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE clocking (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                start TEXT NOT NULL,
+                end TEXT NULL,
+                notes TEXT NULL
+             );
+             INSERT INTO clocking (title, start, end, notes)
+                VALUES ('Old', '2020-01-01T08:00:00+00:00', '2020-01-01T09:00:00+00:00', 'old notes');",
+        )
+        .unwrap();
+
+        assert_eq!(SqliteStore::migrate(&mut conn, MIGRATIONS), Ok(()));
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as u32);
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM clocking", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn migrate_failure_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            "CREATE TABLE a (id INTEGER PRIMARY KEY);",
+            "CREATE TABLE b (id INTEGER PRIMARY KEY); INSERT INTO missing VALUES (1);",
+        ];
+        assert!(SqliteStore::migrate(&mut conn, &migrations).is_err());
+        assert_eq!(user_version(&conn), 1);
+        let b_exists: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE name = 'b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(b_exists, 0, "failed migration should be rolled back");
+    }
+
+    #[test]
+    fn migrate_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let newer = MIGRATIONS.len() as u32 + 1;
+        conn.pragma_update(None, "user_version", newer).unwrap();
+        assert_eq!(
+            SqliteStore::migrate(&mut conn, MIGRATIONS),
+            Err(Error::SchemaTooNew(newer, MIGRATIONS.len() as u32))
+        );
+    }
+
+    #[test]
+    fn corrupt_rows() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let query_start = Utc::now() - chrono::Duration::days(1);
+        let start = Utc::now() - chrono::Duration::minutes(10);
+        for (title, start, end) in [
+            ("Good", start.to_rfc3339(), Utc::now().to_rfc3339()),
+            (
+                "Bad start",
+                "yesterday".to_string(),
+                Utc::now().to_rfc3339(),
+            ),
+            ("Bad end", start.to_rfc3339(), "9999-bad".to_string()),
+        ] {
+            mem_store
+                .conn
+                .execute(
+                    "INSERT INTO clocking (title, start, end) VALUES (?, ?, ?)",
+                    [title, &start, &end],
+                )
+                .unwrap();
+        }
+
+        // rows are decoded in order of start, "Bad end" comes first
+        match mem_store.finished(&query_start, Some(Utc::now()), None) {
+            Err(Error::ImpossibleState(msg)) => assert!(msg.starts_with("corrupt row 3: end")),
+            other => panic!("Should fail by corrupt row, got {other:?}"),
+        }
+
+        mem_store.skip_corrupt(true);
+        let finished = mem_store
+            .finished(&query_start, Some(Utc::now()), None)
+            .unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].id.title, "Good");
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let now = Utc::now();
+        let minutes_ago = |m| now - chrono::Duration::minutes(m);
+        assert_eq!(mem_store.try_pause_any(&now), Ok(None));
+
+        let entry = UnfinishedEntry {
+            id: EntryId {
+                title: "Paused".into(),
+                start: minutes_ago(120),
+            },
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        mem_store.start_entry(&entry).unwrap();
+        assert_eq!(mem_store.try_resume_any(&now), Ok(None), "not paused");
+        assert!(matches!(
+            mem_store.try_pause_any(&minutes_ago(121)),
+            Err(Error::InvalidInput(_))
+        ));
+
+        assert_eq!(
+            mem_store.try_pause_any(&minutes_ago(90)),
+            Ok(Some("Paused".to_string()))
+        );
+        assert_eq!(mem_store.try_pause_any(&now), Ok(None), "paused already");
+        assert!(mem_store.unfinished(1).unwrap()[0].is_paused());
+        assert!(matches!(
+            mem_store.try_resume_any(&minutes_ago(100)),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(
+            mem_store.try_resume_any(&minutes_ago(60)),
+            Ok(Some("Paused".to_string()))
+        );
+        assert!(!mem_store.unfinished(1).unwrap()[0].is_paused());
+
+        // finishing ends the open pause
+        mem_store.try_pause_any(&minutes_ago(30)).unwrap();
+        assert_eq!(
+            mem_store.try_finish_entry(&entry.id, &minutes_ago(10), ""),
+            Ok(true)
+        );
+        let finished = mem_store.latest_finished("Paused").unwrap().unwrap();
+        assert_eq!(
+            finished.pauses,
+            vec![
+                Pause {
+                    start: minutes_ago(90),
+                    end: Some(minutes_ago(60)),
+                },
+                Pause {
+                    start: minutes_ago(30),
+                    end: Some(minutes_ago(10)),
+                },
+            ]
+        );
+        assert_eq!(finished.active_duration(), chrono::Duration::minutes(60));
+
+        mem_store.delete_entry(&entry.id).unwrap();
+        let count: i64 = mem_store
+            .conn
+            .query_row("SELECT count(*) FROM pause", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0, "pauses of deleted entry are removed");
+    }
+
+    #[test]
+    fn switch_entries() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        assert_eq!(mem_store.switch_to("First", ""), Ok(None));
+        let first = mem_store.unfinished(1).unwrap().pop().unwrap();
+        assert_eq!(first.id.title, "First");
+
+        let mut second = gen_entries(1).pop().unwrap();
+        second.id.start = first.id.start + chrono::Duration::minutes(30);
+        mem_store
+            .try_pause_any(&(first.id.start + chrono::Duration::minutes(10)))
+            .unwrap();
+        assert_eq!(
+            mem_store.switch_to_entry(&second, "done"),
+            Ok(Some("First".to_string()))
+        );
+        let finished = mem_store.latest_finished("First").unwrap().unwrap();
+        assert_eq!(finished.end, second.id.start, "no gap between entries");
+        assert_eq!(finished.notes, "done");
+        assert_eq!(finished.pauses[0].end, Some(second.id.start));
+        assert_eq!(mem_store.unfinished(usize::MAX), Ok(vec![second.clone()]));
+
+        // failures leave the ongoing entry untouched
+        let mut early = second.clone();
+        early.id.title = "Early".into();
+        early.id.start = second.id.start - chrono::Duration::minutes(1);
+        assert!(matches!(
+            mem_store.switch_to_entry(&early, ""),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(mem_store.unfinished(usize::MAX), Ok(vec![second]));
+    }
+
+    #[test]
+    fn parallel_tracking() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        assert_eq!(mem_store.tracking_mode(), Ok(TrackingMode::Single));
+        let entries = gen_entries(4);
+        mem_store.start_entry(&entries[0]).unwrap();
+        assert!(matches!(
+            mem_store.start_entry(&entries[1]),
+            Err(Error::UnfinishedExists(_))
+        ));
+
+        mem_store.set_tracking_mode(TrackingMode::Parallel).unwrap();
+        assert_eq!(mem_store.tracking_mode(), Ok(TrackingMode::Parallel));
+        mem_store.start_entry(&entries[1]).unwrap();
+        mem_store.start_entry(&entries[2]).unwrap();
+        assert!(matches!(
+            mem_store.set_tracking_mode(TrackingMode::Single),
+            Err(Error::UnfinishedExists(_))
+        ));
+
+        let at = Utc::now();
+        assert!(matches!(
+            mem_store.try_pause_any(&at),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(mem_store.try_pause_entry(&entries[1].id, &at), Ok(true));
+        assert_eq!(mem_store.try_pause_entry(&entries[1].id, &at), Ok(false));
+        let paused: Vec<bool> = mem_store
+            .unfinished(usize::MAX)
+            .unwrap()
+            .iter()
+            .map(|e| e.is_paused())
+            .collect();
+        assert_eq!(
+            paused.iter().filter(|p| **p).count(),
+            1,
+            "only the chosen one"
+        );
+        assert_eq!(mem_store.try_resume_entry(&entries[2].id, &at), Ok(false));
+        assert!(matches!(
+            mem_store.try_resume_entry(&entries[1].id, &at),
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(
+            mem_store.try_resume_entry(&entries[1].id, &(at + chrono::Duration::seconds(1))),
+            Ok(true)
+        );
+
+        assert_eq!(
+            mem_store.try_finish_entry(&entries[1].id, &Utc::now(), ""),
+            Ok(true)
+        );
+        assert_eq!(mem_store.unfinished_of(Some("Item 1")), Ok(vec![]));
+        assert_eq!(mem_store.try_finish_any(""), Ok(Some("Item 2".to_string())));
+        assert_eq!(
+            mem_store.unfinished(usize::MAX),
+            Ok(vec![entries[0].clone()])
+        );
+
+        mem_store.set_tracking_mode(TrackingMode::Single).unwrap();
+        assert!(matches!(
+            mem_store.start_entry(&entries[3]),
+            Err(Error::UnfinishedExists(_))
+        ));
+    }
+
+    #[test]
+    fn finish_any_latest_started() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        mem_store.set_tracking_mode(TrackingMode::Parallel).unwrap();
+        let entries = gen_entries(2);
+        // the later started entry is inserted first
+        mem_store.start_entry(&entries[1]).unwrap();
+        mem_store.start_entry(&entries[0]).unwrap();
+
+        assert_eq!(mem_store.try_finish_any(""), Ok(Some("Item 1".to_string())));
+        assert_eq!(
+            mem_store.unfinished(usize::MAX),
+            Ok(vec![entries[0].clone()])
+        );
+        assert_eq!(mem_store.try_finish_any(""), Ok(Some("Item 0".to_string())));
+        assert_eq!(mem_store.try_finish_any(""), Ok(None));
+    }
+
+    #[test]
+    fn start_in_past() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let now = Utc::now();
+        let minutes_ago = |m| now - chrono::Duration::minutes(m);
+        let entry = |title: &str, start| UnfinishedEntry {
+            id: EntryId {
+                title: title.to_string().into(),
+                start,
+            },
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        mem_store
+            .start_entry(&entry("Finished", minutes_ago(60)))
+            .unwrap();
+        mem_store
+            .try_finish_entry(&entry("Finished", minutes_ago(60)).id, &minutes_ago(30), "")
+            .unwrap();
+
+        assert!(matches!(
+            mem_store.start_entry_since(&entry("Future", now + chrono::Duration::minutes(1))),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            mem_store.start_entry_since(&entry("Overlapping", minutes_ago(40))),
+            Err(Error::InvalidInput(_))
+        ));
+        mem_store
+            .start_entry_since(&entry("Late", minutes_ago(30)))
+            .unwrap();
+        mem_store.try_finish_any("").unwrap();
+
+        mem_store.set_tracking_mode(TrackingMode::Parallel).unwrap();
+        mem_store
+            .start_entry_since(&entry("Parallel", minutes_ago(40)))
+            .unwrap();
+    }
+
+    #[test]
+    fn unfinished_of_title() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        mem_store.set_tracking_mode(TrackingMode::Parallel).unwrap();
+        let mut entries = gen_entries(3);
+        entries[2].id.title = "Item 0".into();
+        for entry in entries.iter() {
+            mem_store.start_entry(entry).unwrap();
+        }
+
+        assert_eq!(
+            mem_store.unfinished_of(Some("Item 0")),
+            Ok(vec![entries[2].clone(), entries[0].clone()]),
+            "latest-started first"
+        );
+        assert_eq!(mem_store.unfinished_of(Some("Item 9")), Ok(vec![]));
+        assert_eq!(mem_store.unfinished_of(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn overdue_entries() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        assert_eq!(
+            mem_store.max_entry_length(),
+            Ok(Some(chrono::Duration::hours(
+                crate::DEFAULT_MAX_ENTRY_LENGTH_HOURS
+            )))
+        );
+        let mut entry = gen_entries(1).pop().unwrap();
+        entry.id.start = Utc::now() - chrono::Duration::hours(2);
+        mem_store.start_entry(&entry).unwrap();
+        assert_eq!(mem_store.overdue(), Ok(vec![]));
+
+        mem_store
+            .set_max_entry_length(Some(chrono::Duration::hours(1)))
+            .unwrap();
+        assert_eq!(mem_store.overdue(), Ok(vec![entry]));
+
+        assert!(mem_store
+            .set_max_entry_length(Some(chrono::Duration::seconds(30)))
+            .is_err());
+        mem_store.set_max_entry_length(None).unwrap();
+        assert_eq!(mem_store.max_entry_length(), Ok(None));
+        assert_eq!(mem_store.overdue(), Ok(vec![]));
+
+        let too_long = chrono::Duration::days(100_000_000);
+        assert_eq!(
+            mem_store.set_max_entry_length(Some(too_long)),
+            Err(Error::InvalidInput("maximum entry length is too long"))
+        );
+        // saved by an earlier version without the check
+        mem_store
+            .set_setting(
+                MAX_ENTRY_LENGTH_SETTING,
+                &too_long.num_minutes().to_string(),
+            )
+            .unwrap();
+        assert_eq!(mem_store.overdue(), Ok(vec![]));
+    }
+
+    #[test]
+    fn update_and_delete_entry() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let query_start = Utc::now() - chrono::Duration::days(3);
+        let entries = gen_entries(2);
+        for entry in entries.iter() {
+            mem_store.start_entry(entry).unwrap();
+            mem_store.try_finish_any("").unwrap();
+        }
+        mem_store
+            .add_tags(&entries[0].id, &["work".to_string()])
+            .unwrap();
+
+        let mut updated = mem_store.finished(&query_start, None, None).unwrap()[0].clone();
+        updated.id.title = "Renamed".into();
+        updated.end = updated.id.start + chrono::Duration::minutes(30);
+        updated.notes = "Fixed".into();
+        assert_eq!(mem_store.update_entry(&entries[0].id, &updated), Ok(true));
+        assert_eq!(
+            mem_store.update_entry(&entries[0].id, &updated),
+            Ok(false),
+            "old id should be gone"
+        );
+        assert_eq!(
+            mem_store.finished_covering(&(updated.id.start + chrono::Duration::minutes(10))),
+            Ok(vec![updated.clone()])
+        );
+
+        let mut invalid = updated.clone();
+        invalid.end = invalid.id.start;
+        assert_eq!(
+            mem_store.update_entry(&updated.id, &invalid),
+            Err(Error::InvalidInput("end must be after start"))
+        );
+
+        let mut duplicate = mem_store.finished(&query_start, None, None).unwrap()[1].clone();
+        duplicate.id = updated.id.clone();
+        assert_eq!(
+            mem_store.update_entry(&entries[1].id, &duplicate),
+            Err(Error::DuplicateEntry)
+        );
+
+        assert_eq!(mem_store.delete_entry(&updated.id), Ok(true));
+        assert_eq!(mem_store.delete_entry(&updated.id), Ok(false));
+        assert_eq!(
+            mem_store.finished(&query_start, None, None).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            mem_store.tags(),
+            Ok(vec![]),
+            "tags of deleted entry are removed"
+        );
+    }
+
+    #[test]
+    fn update_entry_clips_pauses() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let start = Utc::now() - chrono::Duration::hours(2);
+        let at = |m| start + chrono::Duration::minutes(m);
+        let pause = |s, e: Option<i64>| Pause {
+            start: at(s),
+            end: e.map(at),
+        };
+        let mut entry = gen_entries(1).pop().unwrap();
+        entry.id.start = start;
+        mem_store.start_entry(&entry).unwrap();
+        mem_store.try_pause_any(&at(10)).unwrap();
+        mem_store.try_resume_any(&at(20)).unwrap();
+        mem_store.try_pause_any(&at(40)).unwrap();
+
+        let mut updated = FinishedEntry {
+            id: EntryId {
+                title: entry.id.title.clone(),
+                start: at(15),
+            },
+            end: at(50),
+            notes: entry.notes.clone(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        assert_eq!(mem_store.update_entry(&entry.id, &updated), Ok(true));
+        let pauses = |store: &SqliteStore| {
+            store.finished(&start, None, None).unwrap()[0]
+                .pauses
+                .clone()
+        };
+        assert_eq!(
+            pauses(&mem_store),
+            vec![pause(15, Some(20)), pause(40, Some(50))],
+            "the open pause is closed at the new end, the other clipped to the new start"
+        );
+
+        let id = updated.id.clone();
+        updated.end = at(30);
+        assert_eq!(mem_store.update_entry(&id, &updated), Ok(true));
+        assert_eq!(pauses(&mem_store), vec![pause(15, Some(20))]);
+    }
+
+    #[test]
+    fn split_entry_around_gap() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let start = Utc::now() - chrono::Duration::hours(2);
+        let at = |m| start + chrono::Duration::minutes(m);
+        let pause = |s, e| Pause {
+            start: at(s),
+            end: Some(at(e)),
+        };
+        let entry = FinishedEntry {
+            id: EntryId {
+                title: "Split".into(),
+                start,
+            },
+            end: at(60),
+            notes: "notes".into(),
+            tags: vec!["work".into()],
+            pauses: vec![pause(10, 20), pause(40, 50)],
+        };
+        mem_store.add_finished_entry(&entry).unwrap();
+        let all = |store: &SqliteStore| store.finished(&start, None, None).unwrap();
+
+        assert!(matches!(
+            mem_store.split_entry(&entry.id, &at(30), &at(60)),
+            Err(Error::InvalidInput(_))
+        ));
+        let mut other = entry.clone();
+        other.id.start = at(45);
+        other.end = at(46);
+        other.pauses = vec![];
+        mem_store.add_finished_entry(&other).unwrap();
+        assert_eq!(
+            mem_store.split_entry(&entry.id, &at(15), &at(45)),
+            Err(Error::DuplicateEntry)
+        );
+        assert_eq!(
+            all(&mem_store),
+            vec![entry.clone(), other.clone()],
+            "rolled back"
+        );
+        mem_store.delete_entry(&other.id).unwrap();
+
+        assert_eq!(mem_store.split_entry(&entry.id, &at(15), &at(45)), Ok(true));
+        let before = FinishedEntry {
+            end: at(15),
+            pauses: vec![pause(10, 15)],
+            ..entry.clone()
+        };
+        let after = FinishedEntry {
+            id: EntryId {
+                title: "Split".into(),
+                start: at(45),
+            },
+            pauses: vec![pause(45, 50)],
+            ..entry.clone()
+        };
+        assert_eq!(all(&mem_store), vec![before, after]);
+        let missing = EntryId {
+            title: "Missing".into(),
+            start,
+        };
+        assert_eq!(mem_store.split_entry(&missing, &at(5), &at(6)), Ok(false));
+    }
+
+    #[test]
+    fn add_finished_entries() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let start = Utc::now() - chrono::Duration::hours(3);
+        let entry = FinishedEntry {
+            id: EntryId {
+                title: "Meeting".into(),
+                start,
+            },
+            end: start + chrono::Duration::hours(1),
+            notes: "Happened before".into(),
+            tags: vec!["work".into()],
+            pauses: vec![],
+        };
+        assert_eq!(mem_store.add_finished_entry(&entry), Ok(()));
+        assert_eq!(
+            mem_store.add_finished_entry(&entry),
+            Err(Error::DuplicateEntry)
+        );
+        assert_eq!(
+            mem_store.finished(&start, None, None),
+            Ok(vec![entry.clone()])
+        );
+
+        let mut invalid = entry.clone();
+        invalid.id.title = "Invalid".into();
+        invalid.end = start;
+        assert_eq!(
+            mem_store.add_finished_entry(&invalid),
+            Err(Error::InvalidInput("end must be after start"))
+        );
+
+        // rfc3339 can not store years beyond four digits
+        let out_of_range = Err(Error::InvalidInput(
+            "time must be within years 0000 to 9999",
+        ));
+        invalid.id.start = Utc.with_ymd_and_hms(-1, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(mem_store.add_finished_entry(&invalid), out_of_range);
+        let unfinished = UnfinishedEntry {
+            id: invalid.id.clone(),
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        assert_eq!(mem_store.start_entry(&unfinished), out_of_range);
+
+        let minutes = chrono::Duration::minutes;
+        let pause = |s, e: Option<i64>| Pause {
+            start: start + minutes(s),
+            end: e.map(|e| start + minutes(e)),
+        };
+        for (pauses, reason) in [
+            (vec![pause(10, None)], "pause of finished entry must end"),
+            (vec![pause(20, Some(10))], "pause must not end before start"),
+            (vec![pause(-10, Some(10))], "pause must be within the entry"),
+            (vec![pause(50, Some(70))], "pause must be within the entry"),
+            (
+                vec![pause(30, Some(40)), pause(10, Some(35))],
+                "pauses must not overlap",
+            ),
+        ] {
+            let mut invalid = entry.clone();
+            invalid.id.title = "Invalid pauses".into();
+            invalid.pauses = pauses;
+            assert_eq!(
+                mem_store.add_finished_entry(&invalid),
+                Err(Error::InvalidInput(reason))
+            );
+            assert_eq!(
+                mem_store.import_finished(&[invalid], true),
+                Err(Error::InvalidInput(reason))
+            );
+        }
+
+        for (from, to, count) in [
+            (start - minutes(30), start, 0),
+            (start - minutes(30), start + minutes(1), 1),
+            (start + minutes(10), start + minutes(20), 1),
+            (start + minutes(59), start + minutes(90), 1),
+            (entry.end, entry.end + minutes(10), 0),
+        ] {
+            assert_eq!(
+                mem_store.finished_overlapping(&from, &to).unwrap().len(),
+                count
+            );
+        }
+    }
+
+    #[test]
+    fn integrity_report() {
+        let mut mem_store = SqliteStore::new(IN_MEMORY).unwrap();
+        let hours = chrono::Duration::hours;
+        assert!(mem_store.integrity_report(hours(24)).unwrap().is_clean());
+
+        let base = Utc::now() - hours(48);
+        let entry = |title: &str, start: i64, end: i64| FinishedEntry {
+            id: EntryId {
+                title: title.to_string().into(),
+                start: base + hours(start),
+            },
+            end: base + hours(end),
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        let outer = entry("Outer", 0, 5);
+        let inner = entry("Inner", 1, 2);
+        let tail = entry("Tail", 4, 6);
+        let separate = entry("Separate", 6, 7);
+        for e in [&outer, &inner, &tail, &separate] {
+            mem_store.add_finished_entry(e).unwrap();
+        }
+        let inverted = entry("Inverted", 9, 8);
+        mem_store
+            .conn
+            .execute(
+                "INSERT INTO clocking (title, start, end) VALUES (?, ?, ?), ('Corrupt', 'bad', ?)",
+                [
+                    inverted.id.title.as_ref(),
+                    &inverted.id.start.to_rfc3339(),
+                    &inverted.end.to_rfc3339(),
+                    &inverted.end.to_rfc3339(),
+                ],
+            )
+            .unwrap();
+        let stale = UnfinishedEntry {
+            id: EntryId {
+                title: "Stale".into(),
+                start: base + hours(10),
+            },
+            notes: "".into(),
+            tags: vec![],
+            pauses: vec![],
+        };
+        mem_store.start_entry(&stale).unwrap();
+        let mut paused = entry("Paused", 20, 21);
+        mem_store.add_finished_entry(&paused).unwrap();
+        mem_store
+            .conn
+            .execute(
+                "INSERT INTO pause (clocking_id, start) SELECT id, ? FROM clocking WHERE title = 'Paused'",
+                [paused.end.to_rfc3339()],
+            )
+            .unwrap();
+        paused.pauses.push(Pause {
+            start: paused.end,
+            end: None,
+        });
+
+        let report = mem_store.integrity_report(hours(24)).unwrap();
+        assert_eq!(report.overlaps, vec![(outer.clone(), inner), (outer, tail)]);
+        assert_eq!(report.non_positive, vec![inverted]);
+        assert_eq!(report.invalid_pauses, vec![paused]);
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(report.corrupt[0].contains("corrupt row 6: start 'bad'"));
+        assert_eq!(report.stale_unfinished, vec![stale]);
+
+        let report = mem_store.integrity_report(hours(48)).unwrap();
+        assert!(report.stale_unfinished.is_empty());
+    }
+
     fn gen_entries(count: usize) -> Vec<UnfinishedEntry<'static>> {
         let five_mins = chrono::Duration::minutes(5);
         (0..count)
@@ -287,6 +2096,8 @@ mod tests {
                         start: Utc::now().checked_sub_signed(start_offset).unwrap(),
                     },
                     notes: format!("Init notes for item {i}\n").into(),
+                    tags: vec![],
+                    pauses: vec![],
                 }
             })
             .collect()
@@ -0,0 +1,240 @@
+//! Parse user input of local date times.
+use crate::errors::Error;
+use chrono::prelude::*;
+
+const INVALID_TIME: &str = "time should be 'now', 'HH:MM', 'yesterday HH:MM', 'YYYY-MM-DD HH:MM', \
+     '20m ago', '-1h30m' or RFC 3339 like '2023-01-31T09:30:00+08:00'";
+const INVALID_DURATION: &str = "duration should be like '45m', '1h30m', '2h' or '1d'";
+const TIME_OUT_OF_RANGE: &str = "time is out of range";
+const DURATION_OUT_OF_RANGE: &str = "duration is too long";
+
+/// Parse `input` as a local date time relative to `now`.
+///
+/// Accepts `now`, `HH:MM[:SS]` of today, `yesterday HH:MM[:SS]`, `YYYY-MM-DD HH:MM[:SS]`
+/// (or with `T` as separator), durations before now like `20m ago` or `-1h30m`,
+/// and RFC 3339 date times with offset.
+pub fn parse_datetime<Tz: TimeZone>(
+    input: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<Utc>, Error> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("now") {
+        return Ok(now.with_timezone(&Utc));
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let ago = input
+        .strip_suffix("ago")
+        .or_else(|| input.strip_prefix('-'))
+        .map(|d| parse_duration(d).map_err(|_| Error::InvalidInput(INVALID_TIME)));
+    if let Some(ago) = ago {
+        return now
+            .with_timezone(&Utc)
+            .checked_sub_signed(ago?)
+            .ok_or(Error::InvalidInput(TIME_OUT_OF_RANGE));
+    }
+
+    for (prefix, days_before) in [("today", 0), ("yesterday", 1)] {
+        if let Some(time) = input.strip_prefix(prefix) {
+            let time = parse_time(time.trim()).ok_or(Error::InvalidInput(INVALID_TIME))?;
+            let date = now
+                .date_naive()
+                .checked_sub_signed(chrono::Duration::days(days_before))
+                .ok_or(Error::InvalidInput(TIME_OUT_OF_RANGE))?;
+            return from_local(&date.and_time(time), &now.timezone());
+        }
+    }
+
+    let naive = parse_time(input)
+        .map(|t| now.date_naive().and_time(t))
+        .or_else(|| {
+            [
+                "%Y-%m-%d %H:%M",
+                "%Y-%m-%d %H:%M:%S",
+                "%Y-%m-%dT%H:%M",
+                "%Y-%m-%dT%H:%M:%S",
+            ]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(input, f).ok())
+        })
+        .ok_or(Error::InvalidInput(INVALID_TIME))?;
+
+    from_local(&naive, &now.timezone())
+}
+
+/// Parse a positive duration of units `d`, `h`, `m` and `s`, like `1h30m`.
+pub fn parse_duration(input: &str) -> Result<chrono::Duration, Error> {
+    let input = input.trim();
+    let mut total = chrono::Duration::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let n: i64 = number
+            .parse()
+            .map_err(|_| Error::InvalidInput(INVALID_DURATION))?;
+        number.clear();
+        let unit_seconds = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(Error::InvalidInput(INVALID_DURATION)),
+        };
+        // durations are bounded by milliseconds in i64
+        let seconds = n
+            .checked_mul(unit_seconds)
+            .filter(|s| *s <= chrono::Duration::max_value().num_seconds())
+            .ok_or(Error::InvalidInput(DURATION_OUT_OF_RANGE))?;
+        total = total
+            .checked_add(&chrono::Duration::seconds(seconds))
+            .ok_or(Error::InvalidInput(DURATION_OUT_OF_RANGE))?;
+    }
+
+    if !number.is_empty() || total <= chrono::Duration::zero() {
+        Err(Error::InvalidInput(INVALID_DURATION))
+    } else {
+        Ok(total)
+    }
+}
+
+/// Format `duration` in units accepted by [`parse_duration`], like `1d2h30m`, seconds are dropped.
+pub fn format_duration(duration: &chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes <= 0 {
+        return "0m".to_string();
+    }
+
+    let units = [
+        (minutes / (24 * 60), 'd'),
+        (minutes / 60 % 24, 'h'),
+        (minutes % 60, 'm'),
+    ];
+    units
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{n}{unit}"))
+        .collect()
+}
+
+pub(crate) fn parse_time(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .ok()
+}
+
+/// Resolve a naive local date time in `tz`, the earlier one is taken if it's ambiguous.
+pub(crate) fn from_local<Tz: TimeZone>(
+    naive: &NaiveDateTime,
+    tz: &Tz,
+) -> Result<DateTime<Utc>, Error> {
+    tz.from_local_datetime(naive)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or(Error::InvalidInput("time does not exist in local timezone"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_absolute() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = tz.with_ymd_and_hms(2023, 1, 31, 18, 0, 0).unwrap();
+        let expected = |h, m| {
+            tz.with_ymd_and_hms(2023, 1, 31, h, m, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        assert_eq!(parse_datetime("now", &now), Ok(now.with_timezone(&Utc)));
+        assert_eq!(parse_datetime("09:30", &now), Ok(expected(9, 30)));
+        assert_eq!(
+            parse_datetime(" 2023-01-31 09:30 ", &now),
+            Ok(expected(9, 30))
+        );
+        assert_eq!(
+            parse_datetime("2023-01-31T09:30:00", &now),
+            Ok(expected(9, 30))
+        );
+        assert_eq!(
+            parse_datetime("2023-01-31T01:30:00Z", &now),
+            Ok(expected(9, 30))
+        );
+        for bad in [
+            "",
+            "9.30",
+            "25:00",
+            "2023-02-30 10:00",
+            "yesterday",
+            "tomorrow 10:00",
+        ] {
+            assert!(
+                parse_datetime(bad, &now).is_err(),
+                "'{bad}' should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_relative() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let now = tz.with_ymd_and_hms(2023, 1, 31, 0, 30, 0).unwrap();
+        let now_utc = now.with_timezone(&Utc);
+
+        assert_eq!(
+            parse_datetime("20m ago", &now),
+            Ok(now_utc - chrono::Duration::minutes(20))
+        );
+        assert_eq!(
+            parse_datetime("-1h30m", &now),
+            Ok(now_utc - chrono::Duration::minutes(90))
+        );
+        assert_eq!(
+            parse_datetime("yesterday 23:00", &now),
+            Ok(now_utc - chrono::Duration::minutes(90))
+        );
+        assert_eq!(
+            parse_datetime("today 00:10", &now),
+            Ok(now_utc - chrono::Duration::minutes(20))
+        );
+        assert!(parse_datetime("x ago", &now).is_err());
+        assert_eq!(
+            parse_datetime("-99999999999d", &now),
+            Err(Error::InvalidInput(TIME_OUT_OF_RANGE))
+        );
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("45m"), Ok(chrono::Duration::minutes(45)));
+        assert_eq!(parse_duration(" 1h30m "), Ok(chrono::Duration::minutes(90)));
+        assert_eq!(
+            parse_duration("1d2h3m4s"),
+            Ok(chrono::Duration::seconds(86400 + 2 * 3600 + 3 * 60 + 4))
+        );
+        assert_eq!(
+            format_duration(&chrono::Duration::seconds(86400 + 2 * 3600 + 4)),
+            "1d2h"
+        );
+        assert_eq!(format_duration(&chrono::Duration::minutes(90)), "1h30m");
+        assert_eq!(format_duration(&chrono::Duration::seconds(59)), "0m");
+        for bad in ["", "45", "m", "1x", "0m", "1h 30m"] {
+            assert!(parse_duration(bad).is_err(), "'{bad}' should be invalid");
+        }
+        for too_long in ["99999999999999999d", "999999999999999m", "106751991167d1d"] {
+            assert_eq!(
+                parse_duration(too_long),
+                Err(Error::InvalidInput(DURATION_OUT_OF_RANGE)),
+                "{too_long}"
+            );
+        }
+    }
+}
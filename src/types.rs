@@ -1,24 +1,208 @@
-use crate::strify_duration;
+use crate::{strify_cents, strify_duration};
 use chrono::prelude::*;
 #[cfg(feature = "http")]
-use pulldown_cmark::{html, Parser};
-use serde::Serialize;
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
+use std::str::FromStr;
 
 /// Identify an unique clocking entity
-#[derive(Serialize, PartialEq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct EntryId<'a> {
     pub title: Cow<'a, str>,
     pub start: DateTime<Utc>,
 }
 
+/// Whether multiple unfinished entries may run at the same time, configured per store.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackingMode {
+    /// At most one unfinished entry, starting another one fails.
+    #[default]
+    Single,
+    /// Any number of unfinished entries.
+    Parallel,
+}
+
+impl FromStr for TrackingMode {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(TrackingMode::Single),
+            "parallel" => Ok(TrackingMode::Parallel),
+            _ => Err(crate::errors::Error::InvalidInput(
+                "tracking mode should be 'single' or 'parallel'",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TrackingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackingMode::Single => write!(f, "single"),
+            TrackingMode::Parallel => write!(f, "parallel"),
+        }
+    }
+}
+
+/// Weekdays from Monday.
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+const INVALID_HOURS: &str = "working hours should be like '09:00-17:00' or 'off'";
+const INVALID_WORKDAY: &str = "workday should be 7 comma separated working hours from Monday";
+
+/// Local working hours of each weekday, configured per store.
+///
+/// [`crate::views::DailyDistributionView`] shows idle time within them, days without hours are off.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Workday([Option<(NaiveTime, NaiveTime)>; 7]);
+
+impl Default for Workday {
+    fn default() -> Self {
+        let start = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let end = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
+        Workday([Some((start, end)); 7])
+    }
+}
+
+impl Workday {
+    /// Working hours of `weekday`, None if it's off.
+    pub fn hours(&self, weekday: Weekday) -> Option<(NaiveTime, NaiveTime)> {
+        self.0[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Change working hours of `weekday`, the end must be after the start.
+    pub fn set_hours(
+        &mut self,
+        weekday: Weekday,
+        hours: Option<(NaiveTime, NaiveTime)>,
+    ) -> Result<(), crate::errors::Error> {
+        if matches!(hours, Some((start, end)) if end <= start) {
+            return Err(crate::errors::Error::InvalidInput(
+                "end of working hours must be after start",
+            ));
+        }
+        self.0[weekday.num_days_from_monday() as usize] = hours;
+        Ok(())
+    }
+
+    /// Override the start or end of every working day, days off are kept off.
+    pub fn with_bounds(
+        mut self,
+        start: Option<NaiveTime>,
+        end: Option<NaiveTime>,
+    ) -> Result<Self, crate::errors::Error> {
+        for weekday in WEEKDAYS {
+            if let Some((day_start, day_end)) = self.hours(weekday) {
+                let hours = (start.unwrap_or(day_start), end.unwrap_or(day_end));
+                self.set_hours(weekday, Some(hours))?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Parse working hours like `09:00-17:00`, or `off` as None.
+    pub fn parse_hours(s: &str) -> Result<Option<(NaiveTime, NaiveTime)>, crate::errors::Error> {
+        if s.trim() == "off" {
+            return Ok(None);
+        }
+        let times = s.split_once('-').map(|(start, end)| {
+            (
+                crate::timeparse::parse_time(start.trim()),
+                crate::timeparse::parse_time(end.trim()),
+            )
+        });
+        match times {
+            Some((Some(start), Some(end))) => Ok(Some((start, end))),
+            _ => Err(crate::errors::Error::InvalidInput(INVALID_HOURS)),
+        }
+    }
+
+    fn format_hours(hours: Option<(NaiveTime, NaiveTime)>) -> String {
+        match hours {
+            Some((start, end)) => format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+            None => "off".to_string(),
+        }
+    }
+}
+
+/// The inverse of [`fmt::Display`], e.g. `09:00-17:00,09:00-17:00,...,off,off`.
+impl FromStr for Workday {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let days: Vec<&str> = s.split(',').collect();
+        if days.len() != 7 {
+            return Err(crate::errors::Error::InvalidInput(INVALID_WORKDAY));
+        }
+        let mut workday = Workday([None; 7]);
+        for (weekday, day) in WEEKDAYS.into_iter().zip(days) {
+            workday.set_hours(weekday, Workday::parse_hours(day)?)?;
+        }
+        Ok(workday)
+    }
+}
+
+impl fmt::Display for Workday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days: Vec<String> = self.0.iter().map(|h| Workday::format_hours(*h)).collect();
+        write!(f, "{}", days.join(","))
+    }
+}
+
+/// Serialized as `{"mon": "08:00-21:00", ..., "sun": "off"}`.
+impl Serialize for Workday {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(WEEKDAYS.iter().map(|weekday| {
+            (
+                weekday.to_string().to_lowercase(),
+                Workday::format_hours(self.hours(*weekday)),
+            )
+        }))
+    }
+}
+
+/// A paused interval of a clocking entry.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Pause {
+    pub start: DateTime<Utc>,
+    /// None if the entry is still paused.
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for Pause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.start.with_timezone(&Local).format(TIME_FORMAT);
+        match self.end {
+            Some(end) => write!(
+                f,
+                "{start} ~ {}",
+                end.with_timezone(&Local).format(TIME_FORMAT)
+            ),
+            None => write!(f, "{start} ~"),
+        }
+    }
+}
+
 /// Represent an unfinished clocking entity.
 #[derive(Serialize, PartialEq, Clone, Debug)]
 pub struct UnfinishedEntry<'a> {
     pub id: EntryId<'a>,
     pub notes: Cow<'a, str>,
+    pub tags: Vec<String>,
+    /// Paused intervals ordered by start, only the last one may be open.
+    pub pauses: Vec<Pause>,
 }
 
 const TIME_FORMAT: &str = "%Y-%m-%d %a %H:%M";
@@ -26,6 +210,21 @@ impl<'a> UnfinishedEntry<'a> {
     pub fn started_minutes(&self) -> i64 {
         (Utc::now() - self.id.start).num_minutes()
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.pauses.last().is_some_and(|p| p.end.is_none())
+    }
+
+    /// The entry as if finished at `end`, e.g. to count its time so far in views.
+    pub fn finished_at(&self, end: DateTime<Utc>) -> FinishedEntry<'a> {
+        FinishedEntry {
+            id: self.id.clone(),
+            end,
+            notes: self.notes.clone(),
+            tags: self.tags.clone(),
+            pauses: self.pauses.clone(),
+        }
+    }
 }
 
 impl<'a> fmt::Display for UnfinishedEntry<'a> {
@@ -35,6 +234,11 @@ impl<'a> fmt::Display for UnfinishedEntry<'a> {
             "\tStarted at: {}",
             self.id.start.with_timezone(&Local).format(TIME_FORMAT)
         ));
+        r = r.and_then(|_| write_pauses(f, &self.pauses));
+
+        if !self.tags.is_empty() {
+            r = r.and_then(|_| writeln!(f, "\tTags: {}", self.tags.join(", ")));
+        }
 
         if !self.notes.is_empty() {
             r = r.and(writeln!(f, "\tNotes:"));
@@ -48,32 +252,94 @@ impl<'a> fmt::Display for UnfinishedEntry<'a> {
 }
 
 /// Represent a finished clocking entry.
-#[derive(Serialize, PartialEq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct FinishedEntry<'a> {
     pub id: EntryId<'a>,
     pub end: DateTime<Utc>,
+    #[serde(default)]
     pub notes: Cow<'a, str>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Paused intervals ordered by start, not counted as active time.
+    #[serde(default)]
+    pub pauses: Vec<Pause>,
+}
+
+impl<'a> FinishedEntry<'a> {
+    /// Active intervals between `start` and `end` excluding pauses.
+    ///
+    /// Pauses are clipped to the entry, an open pause lasts until `end`.
+    pub fn active_spans(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut spans = vec![];
+        let mut current = self.id.start;
+        for pause in self.pauses.iter() {
+            let pause_start = pause.start.clamp(current, self.end.max(current));
+            if pause_start > current {
+                spans.push((current, pause_start));
+            }
+            current = current.max(pause.end.unwrap_or(self.end));
+        }
+        if self.end > current {
+            spans.push((current, self.end));
+        }
+        spans
+    }
+
+    /// Duration between `start` and `end` excluding pauses, zero if `end` is not after `start`.
+    pub fn active_duration(&self) -> chrono::Duration {
+        self.active_spans()
+            .iter()
+            .fold(chrono::Duration::zero(), |acc, (start, end)| {
+                acc + (*end - *start)
+            })
+    }
+}
+
+fn write_pauses(f: &mut fmt::Formatter<'_>, pauses: &[Pause]) -> fmt::Result {
+    let mut r: fmt::Result = Ok(());
+    for pause in pauses.iter() {
+        r = r.and_then(|_| writeln!(f, "\tPaused: {pause}"));
+    }
+    r
 }
 
 #[cfg(feature = "http")]
 impl<'a> FinishedEntry<'a> {
     pub fn html_segment(&self) -> String {
+        let tags = if self.tags.is_empty() {
+            String::new()
+        } else {
+            format!("*{}*\n\n", self.tags.join(", "))
+        };
+        let pauses: String = self
+            .pauses
+            .iter()
+            .map(|p| format!("Paused: {p}\n\n"))
+            .collect();
         let text = format!(
-            "## {}\n **{}** ~ **{}** \n\n {}",
+            "## {}\n **{}** ~ **{}** \n\n {}{}{}",
             &self.id.title,
             self.id.start.with_timezone(&Local).format(TIME_FORMAT),
             self.end.with_timezone(&Local).format(TIME_FORMAT),
+            pauses,
+            tags,
             &self.notes
         );
 
-        let parser = Parser::new(&text);
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
-
-        html_output
+        render_markdown(&text, Options::empty())
     }
 }
 
+/// Render markdown `text` as html with extensions of `options`.
+#[cfg(feature = "http")]
+pub(crate) fn render_markdown(text: &str, options: Options) -> String {
+    let parser = Parser::new_ext(text, options);
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, parser);
+
+    html_output
+}
+
 impl<'a> fmt::Display for FinishedEntry<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut r = writeln!(f, "{}:", &self.id.title).and(writeln!(
@@ -82,6 +348,11 @@ impl<'a> fmt::Display for FinishedEntry<'a> {
             self.id.start.with_timezone(&Local).format(TIME_FORMAT),
             self.end.with_timezone(&Local).format(TIME_FORMAT),
         ));
+        r = r.and_then(|_| write_pauses(f, &self.pauses));
+
+        if !self.tags.is_empty() {
+            r = r.and_then(|_| writeln!(f, "\tTags: {}", self.tags.join(", ")));
+        }
 
         if !self.notes.is_empty() {
             r = r.and(writeln!(f, "\tNotes:"));
@@ -94,15 +365,426 @@ impl<'a> fmt::Display for FinishedEntry<'a> {
     }
 }
 
+/// Problems found by [`crate::ClockingStore::integrity_report`].
+#[derive(Serialize, PartialEq, Clone, Debug, Default)]
+pub struct IntegrityReport<'a> {
+    /// Pairs of overlapping finished entries, the first one starts no later than the second.
+    pub overlaps: Vec<(FinishedEntry<'a>, FinishedEntry<'a>)>,
+    /// Finished entries whose end is not after start.
+    pub non_positive: Vec<FinishedEntry<'a>>,
+    /// Finished entries with pauses open, out of the entry or overlapping each other.
+    pub invalid_pauses: Vec<FinishedEntry<'a>>,
+    /// Descriptions of rows which can not be decoded, e.g. unparsable timestamps.
+    pub corrupt: Vec<String>,
+    /// Unfinished entries started too long ago.
+    pub stale_unfinished: Vec<UnfinishedEntry<'a>>,
+}
+
+impl<'a> IntegrityReport<'a> {
+    pub fn is_clean(&self) -> bool {
+        self.overlaps.is_empty()
+            && self.non_positive.is_empty()
+            && self.invalid_pauses.is_empty()
+            && self.corrupt.is_empty()
+            && self.stale_unfinished.is_empty()
+    }
+}
+
+impl<'a> fmt::Display for IntegrityReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut r: fmt::Result = Ok(());
+        if self.is_clean() {
+            return writeln!(f, "(No problem found)");
+        }
+
+        if !self.overlaps.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Overlapping entries:"));
+            for (first, second) in self.overlaps.iter() {
+                r = r.and_then(|_| writeln!(f, "{first}{second}"));
+            }
+        }
+        if !self.non_positive.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Entries not ending after start:"));
+            for entry in self.non_positive.iter() {
+                r = r.and_then(|_| write!(f, "{entry}"));
+            }
+        }
+        if !self.invalid_pauses.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Entries with invalid pauses:"));
+            for entry in self.invalid_pauses.iter() {
+                r = r.and_then(|_| write!(f, "{entry}"));
+            }
+        }
+        if !self.corrupt.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Corrupt entries:"));
+            for desc in self.corrupt.iter() {
+                r = r.and_then(|_| writeln!(f, "\t{desc}"));
+            }
+        }
+        if !self.stale_unfinished.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Stale unfinished entries:"));
+            for entry in self.stale_unfinished.iter() {
+                r = r.and_then(|_| write!(f, "{entry}"));
+            }
+        }
+        r
+    }
+}
+
+/// Which durations are rounded by a [`Rounding`].
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundScope {
+    /// Time of each entry on each date, before summing them up.
+    #[default]
+    Entry,
+    /// Total of each title on each date, day totals are sums of them in every view.
+    Day,
+}
+
+impl FromStr for RoundScope {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "entry" => Ok(RoundScope::Entry),
+            "day" => Ok(RoundScope::Day),
+            _ => Err(crate::errors::Error::InvalidInput(
+                "rounding scope should be 'entry' or 'day'",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RoundScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundScope::Entry => write!(f, "entry"),
+            RoundScope::Day => write!(f, "day"),
+        }
+    }
+}
+
+const INVALID_ROUNDING: &str = "rounding should be like '15m', '15m-up' or '6m-nearest'";
+
+/// Round durations in reports to multiples of `minutes`, like `15m-up` or `6m-nearest`.
+#[derive(Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Rounding {
+    pub minutes: u32,
+    /// Round up instead of to the nearest, halves are rounded up either way.
+    pub up: bool,
+    pub scope: RoundScope,
+}
+
+impl Rounding {
+    pub fn round(&self, duration: chrono::Duration) -> chrono::Duration {
+        let step = i64::from(self.minutes) * 60;
+        let seconds = duration.num_seconds();
+        let steps = if self.up {
+            (seconds + step - 1).div_euclid(step)
+        } else {
+            (seconds + step / 2).div_euclid(step)
+        };
+        chrono::Duration::seconds(steps * step)
+    }
+}
+
+/// Parse `15m`, `15m-up` or `15m-nearest` rounding per [`RoundScope::Entry`].
+impl FromStr for Rounding {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::errors::Error::InvalidInput(INVALID_ROUNDING);
+        let (step, direction) = s.trim().split_once('-').unwrap_or((s.trim(), "nearest"));
+        let minutes: u32 = step
+            .strip_suffix('m')
+            .and_then(|m| m.parse().ok())
+            .filter(|m| *m > 0)
+            .ok_or_else(invalid)?;
+        let up = match direction {
+            "up" => true,
+            "nearest" => false,
+            _ => return Err(invalid()),
+        };
+        Ok(Rounding {
+            minutes,
+            up,
+            scope: RoundScope::default(),
+        })
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.up { "up" } else { "nearest" };
+        write!(f, "{}m-{direction} per {}", self.minutes, self.scope)
+    }
+}
+
+/// Separator of projects and sub projects in titles, e.g. `clocking/server`.
+pub const PROJECT_SEPARATOR: char = '/';
+
+/// Projects of `title` from the top level, `clocking / server` is `["clocking", "server"]`.
+pub fn project_path(title: &str) -> impl Iterator<Item = &str> {
+    title
+        .split(PROJECT_SEPARATOR)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether `title` is `project` or one of its sub projects, `acme/website` is in `acme`.
+pub fn in_project(title: &str, project: &str) -> bool {
+    let mut path = project_path(title);
+    project_path(project).all(|name| path.next() == Some(name))
+}
+
+/// Hourly rate billed to a client for a project, which applies to its sub projects too.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Rate {
+    /// A title or its project, like `acme` for `acme/website`.
+    pub project: String,
+    pub client: String,
+    /// In cents, or hundredths of whatever the currency is.
+    pub cents_per_hour: i64,
+}
+
+impl Rate {
+    /// Whether `title` is of the project or one of its sub projects.
+    pub fn applies_to(&self, title: &str) -> bool {
+        in_project(title, &self.project)
+    }
+
+    /// The rate of the most specific project of `title` in `rates`.
+    pub fn find<'r>(rates: &'r [Rate], title: &str) -> Option<&'r Rate> {
+        rates
+            .iter()
+            .filter(|rate| rate.applies_to(title))
+            .max_by_key(|rate| project_path(&rate.project).count())
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}/h ({})",
+            self.project,
+            strify_cents(self.cents_per_hour),
+            self.client
+        )
+    }
+}
+
+/// Period a [`Goal`] is tracked over, weeks start on a configurable weekday.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+impl FromStr for GoalPeriod {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(GoalPeriod::Day),
+            "week" => Ok(GoalPeriod::Week),
+            _ => Err(crate::errors::Error::InvalidInput(
+                "goal period should be 'day' or 'week'",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GoalPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalPeriod::Day => write!(f, "day"),
+            GoalPeriod::Week => write!(f, "week"),
+        }
+    }
+}
+
+/// Whether a [`Goal`] is time to spend at least, or at most.
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalKind {
+    Target,
+    Cap,
+}
+
+impl FromStr for GoalKind {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "target" => Ok(GoalKind::Target),
+            "cap" => Ok(GoalKind::Cap),
+            _ => Err(crate::errors::Error::InvalidInput(
+                "goal kind should be 'target' or 'cap'",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GoalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalKind::Target => write!(f, "target"),
+            GoalKind::Cap => write!(f, "cap"),
+        }
+    }
+}
+
+/// Entries a [`Goal`] counts, written as `tag:meeting` for a tag or the title otherwise.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
+pub enum GoalSubject {
+    /// Entries of the title or its sub projects, see [`in_project`].
+    Title(String),
+    Tag(String),
+}
+
+impl GoalSubject {
+    pub fn matches(&self, title: &str, tags: &[String]) -> bool {
+        match self {
+            GoalSubject::Title(project) => in_project(title, project),
+            GoalSubject::Tag(tag) => tags.contains(tag),
+        }
+    }
+}
+
+impl FromStr for GoalSubject {
+    type Err = crate::errors::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let subject = match s.trim().strip_prefix("tag:") {
+            Some(tag) => GoalSubject::Tag(tag.trim().to_string()),
+            None => GoalSubject::Title(project_path(s).collect::<Vec<_>>().join("/")),
+        };
+        match &subject {
+            GoalSubject::Title(name) | GoalSubject::Tag(name) if name.is_empty() => Err(
+                crate::errors::Error::InvalidInput("goal should be of a title or 'tag:name'"),
+            ),
+            _ => Ok(subject),
+        }
+    }
+}
+
+impl fmt::Display for GoalSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalSubject::Title(title) => write!(f, "{title}"),
+            GoalSubject::Tag(tag) => write!(f, "tag:{tag}"),
+        }
+    }
+}
+
+/// Serialized as [`GoalSubject`]'s display, like `tag:meeting`.
+impl Serialize for GoalSubject {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Time to spend on, or at most on, a title or tag each day or week, like 8h a week on reading.
+#[derive(Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct Goal {
+    pub subject: GoalSubject,
+    pub period: GoalPeriod,
+    pub kind: GoalKind,
+    #[serde(rename = "seconds", serialize_with = "serialize_seconds")]
+    pub duration: chrono::Duration,
+}
+
+#[cfg(test)]
+impl Goal {
+    /// Goal of `hours` on `subject` like `tag:meeting`, which must be valid.
+    pub(crate) fn of_hours(subject: &str, period: GoalPeriod, kind: GoalKind, hours: i64) -> Goal {
+        Goal {
+            subject: subject.parse().unwrap(),
+            period,
+            kind,
+            duration: chrono::Duration::hours(hours),
+        }
+    }
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    duration: &chrono::Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(duration.num_seconds())
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} {} a {}",
+            self.subject,
+            self.kind,
+            strify_duration(&self.duration),
+            self.period
+        )
+    }
+}
+
+/// Result of [`crate::ClockingStore::import_finished`].
+#[derive(Serialize, PartialEq, Clone, Debug, Default)]
+pub struct ImportReport<'a> {
+    /// Nothing was saved, `imported` counts the entries which would be.
+    pub dry_run: bool,
+    pub imported: usize,
+    /// Ids of entries skipped since they exist already.
+    pub duplicates: Vec<EntryId<'a>>,
+    /// Rows of the source which can not be imported, with reasons.
+    pub skipped: Vec<String>,
+}
+
+impl<'a> fmt::Display for ImportReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut r = if self.dry_run {
+            writeln!(f, "(Would import {} entries)", self.imported)
+        } else {
+            writeln!(f, "(Imported {} entries)", self.imported)
+        };
+        if !self.duplicates.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Skipped duplicates:"));
+            for id in self.duplicates.iter() {
+                r = r.and_then(|_| {
+                    writeln!(
+                        f,
+                        "\t{}: {}",
+                        id.title,
+                        id.start.with_timezone(&Local).format(TIME_FORMAT)
+                    )
+                });
+            }
+        }
+        if !self.skipped.is_empty() {
+            r = r.and_then(|_| writeln!(f, "Skipped rows:"));
+            for desc in self.skipped.iter() {
+                r = r.and_then(|_| writeln!(f, "\t{desc}"));
+            }
+        }
+        r
+    }
+}
+
 /// Represent the time span of a finished clocking entry.
 #[derive(Serialize, Debug, Eq, PartialEq, Clone)]
 pub(crate) struct TimeSpan {
-    start: DateTime<Local>,
-    end: DateTime<Local>,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
 }
 
 impl TimeSpan {
-    pub fn build(start: DateTime<Local>, end: DateTime<Local>) -> Result<Self, &'static str> {
+    /// Both ends keep their own local offsets, which differ if the span crosses a DST change.
+    pub fn build(
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Result<Self, &'static str> {
         if end > start {
             Ok(TimeSpan { start, end })
         } else {
@@ -114,11 +796,11 @@ impl TimeSpan {
         self.end - self.start
     }
 
-    pub fn start(&self) -> DateTime<Local> {
+    pub fn start(&self) -> DateTime<FixedOffset> {
         self.start
     }
 
-    pub fn end(&self) -> DateTime<Local> {
+    pub fn end(&self) -> DateTime<FixedOffset> {
         self.end
     }
 }
@@ -131,7 +813,7 @@ impl Ord for TimeSpan {
 
 impl PartialOrd for TimeSpan {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.start.cmp(&other.start))
+        Some(self.cmp(other))
     }
 }
 
@@ -1,7 +1,10 @@
-use crate::strify_duration;
+use crate::store_helper::{day_start, local_to_utc};
+#[cfg(feature = "http")]
+use crate::types::render_markdown;
 use crate::types::*;
+use crate::{strify_cents, strify_duration};
 use chrono::prelude::*;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use std::cmp::Ordering;
 use std::collections::BTreeMap as Map;
 use std::fmt;
@@ -9,20 +12,93 @@ use std::fmt;
 type TitleDurationMap = Map<String, chrono::Duration>;
 type DateDurationMap = Map<NaiveDate, chrono::Duration>;
 
+/// Serialized record of a duration by title, durations of all serialized views are in whole seconds.
+#[derive(Serialize)]
+struct TitleSeconds<'v> {
+    title: &'v str,
+    seconds: i64,
+}
+
+/// Serialized record of durations by title on a date or period.
+#[derive(Serialize)]
+struct DetailRecord<'v> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<String>,
+    date: &'v NaiveDate,
+    seconds: i64,
+    titles: Vec<TitleSeconds<'v>>,
+}
+
+fn serialize_detail<S: Serializer>(
+    detail: &Map<NaiveDate, TitleDurationMap>,
+    label: impl Fn(&NaiveDate) -> Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(detail.iter().map(|(key, durations)| {
+        DetailRecord {
+            period: label(key),
+            date: key,
+            seconds: durations
+                .values()
+                .fold(chrono::Duration::zero(), |acc, d| acc + *d)
+                .num_seconds(),
+            titles: durations
+                .iter()
+                .map(|(title, d)| TitleSeconds {
+                    title,
+                    seconds: d.num_seconds(),
+                })
+                .collect(),
+        }
+    }))
+}
+
+/// Serialized record of a duration on a date or period.
+#[derive(Serialize)]
+struct SummaryRecord<'v> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<String>,
+    date: &'v NaiveDate,
+    seconds: i64,
+}
+
+fn serialize_summary<S: Serializer>(
+    summary: &DateDurationMap,
+    label: impl Fn(&NaiveDate) -> Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(summary.iter().map(|(key, d)| SummaryRecord {
+        period: label(key),
+        date: key,
+        seconds: d.num_seconds(),
+    }))
+}
+
 /// `EntryDetailView` groups detailed `Effort` (start, end) by `FinishedEntry` title.
-#[derive(Serialize, Debug)]
-pub struct EntryDetailView(Map<String, Vec<TimeSpan>>);
+///
+/// Totals of titles are rounded the same way as [`DailyDetailView`] and summed over dates.
+#[derive(Debug)]
+pub struct EntryDetailView {
+    spans: Map<String, Vec<TimeSpan>>,
+    totals: TitleDurationMap,
+}
+
+/// Serialized as `{"title": [{"start", "end"}]}`.
+impl Serialize for EntryDetailView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.spans.serialize(serializer)
+    }
+}
 
 impl fmt::Display for EntryDetailView {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut r: fmt::Result = Ok(());
-        for (title, efforts) in self.0.iter() {
+        for (title, efforts) in self.spans.iter() {
             r = r.and_then(|_| writeln!(f, "{}:", title));
-            let mut total_duration: chrono::Duration = chrono::Duration::days(0);
             for eff in efforts.iter() {
                 r = r.and_then(|_| writeln!(f, "\t{}", eff));
-                total_duration = total_duration + eff.duration();
             }
+            let total_duration = self.totals[title];
             r = r.and_then(|_| writeln!(f, "\t(Total): {}\n", strify_duration(&total_duration)))
         }
         r
@@ -30,51 +106,241 @@ impl fmt::Display for EntryDetailView {
 }
 
 impl EntryDetailView {
-    pub fn new(entries: &[FinishedEntry]) -> Self {
-        let mut view: Map<String, Vec<TimeSpan>> = Map::new();
+    pub fn new(entries: &[FinishedEntry], rounding: Option<Rounding>) -> Self {
+        let mut spans: Map<String, Vec<TimeSpan>> = Map::new();
         for entry in entries.iter() {
-            view.entry(entry.id.title.to_string())
-                .and_modify(|efforts| {
-                    efforts.push(
-                        // TODO: handle invalid timespan (bad data in database)
-                        TimeSpan::build(
-                            entry.id.start.with_timezone(&Local),
-                            entry.end.with_timezone(&Local),
-                        )
-                        .unwrap(),
-                    );
-                })
-                .or_insert_with(|| {
-                    // TODO: handle invalid timespan (bad data in database)
-                    vec![TimeSpan::build(
-                        entry.id.start.with_timezone(&Local),
-                        entry.end.with_timezone(&Local),
-                    )
-                    .unwrap()]
-                });
+            spans
+                .entry(entry.id.title.to_string())
+                .or_default()
+                .extend(active_time_spans(entry));
+        }
+        // entries ending before start have no active spans, `clocking check` reports them
+        spans.retain(|_, spans| !spans.is_empty());
+
+        EntryDetailView {
+            totals: title_totals(entries, &Local, rounding),
+            spans,
         }
+    }
+}
+
+/// Sum durations of `entries` by title over dates, see [`detail_by_date`].
+fn title_totals<Tz: TimeZone>(
+    entries: &[FinishedEntry],
+    tz: &Tz,
+    rounding: Option<Rounding>,
+) -> TitleDurationMap {
+    let mut totals: TitleDurationMap = Map::new();
+    for titles in detail_by_date(entries, tz, rounding, |date| date).into_values() {
+        for (title, duration) in titles {
+            totals
+                .entry(title)
+                .and_modify(|dur| *dur = *dur + duration)
+                .or_insert(duration);
+        }
+    }
+    totals
+}
+
+/// Split time shared by overlapping entries evenly among them, instead of counting it for each.
+///
+/// Each shared interval is cut into consecutive slices, one for each entry running in it,
+/// the rest of the interval is turned into pauses of the entry.
+/// Active spans of the returned entries do not overlap, so views built from them don't double count.
+pub fn split_overlaps<'a>(entries: &[FinishedEntry<'a>]) -> Vec<FinishedEntry<'a>> {
+    let spans: Vec<Vec<(DateTime<Utc>, DateTime<Utc>)>> =
+        entries.iter().map(|e| e.active_spans()).collect();
+    let mut bounds: Vec<DateTime<Utc>> = spans
+        .iter()
+        .flatten()
+        .flat_map(|(start, end)| [*start, *end])
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+
+    let mut kept: Vec<Vec<(DateTime<Utc>, DateTime<Utc>)>> = vec![vec![]; entries.len()];
+    for window in bounds.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let running: Vec<usize> = (0..entries.len())
+            .filter(|&i| spans[i].iter().any(|(s, e)| *s <= start && end <= *e))
+            .collect();
+        let slice = (end - start) / running.len().max(1) as i32;
+        for (n, &i) in running.iter().enumerate() {
+            let slice_start = start + slice * n as i32;
+            let slice_end = if n + 1 == running.len() {
+                end
+            } else {
+                slice_start + slice
+            };
+            match kept[i].last_mut() {
+                Some(last) if last.1 == slice_start => last.1 = slice_end,
+                _ => kept[i].push((slice_start, slice_end)),
+            }
+        }
+    }
+
+    entries
+        .iter()
+        .zip(kept)
+        .map(|(entry, kept)| {
+            let mut pauses = vec![];
+            let mut current = entry.id.start;
+            for (start, end) in kept.into_iter().chain([(entry.end, entry.end)]) {
+                if start > current {
+                    pauses.push(Pause {
+                        start: current,
+                        end: Some(start),
+                    });
+                }
+                current = end;
+            }
+            FinishedEntry {
+                pauses,
+                ..entry.clone()
+            }
+        })
+        .collect()
+}
+
+/// Active intervals of `entry` excluding pauses, as local [`TimeSpan`]s.
+fn active_time_spans(entry: &FinishedEntry) -> Vec<TimeSpan> {
+    entry
+        .active_spans()
+        .into_iter()
+        .filter_map(|(start, end)| {
+            TimeSpan::build(to_fixed(&start, &Local), to_fixed(&end, &Local)).ok()
+        })
+        .collect()
+}
+
+/// `at` in `tz` with the offset of that time.
+fn to_fixed<Tz: TimeZone>(at: &DateTime<Utc>, tz: &Tz) -> DateTime<FixedOffset> {
+    let local = at.with_timezone(tz);
+    local.with_timezone(&local.offset().fix())
+}
+
+/// Start, end and title of an active interval.
+type TitledSpan = (DateTime<Utc>, DateTime<Utc>, String);
 
-        EntryDetailView(view)
+/// Active intervals of `entry` split at local midnights in `tz`, along with their local dates.
+fn daily_spans<Tz: TimeZone>(
+    entry: &FinishedEntry,
+    tz: &Tz,
+) -> Vec<(NaiveDate, DateTime<Utc>, DateTime<Utc>)> {
+    let mut spans = vec![];
+    for (mut start, end) in entry.active_spans() {
+        while start < end {
+            let date = start.with_timezone(tz).date_naive();
+            let next = day_start(date.succ_opt().unwrap(), tz).min(end);
+            spans.push((date, start, next));
+            start = next;
+        }
     }
+    spans
 }
 
-/// `DailySummaryView` groups summarized [`chrono::Duration`] by local naive date of [`FinishedEntry`] start.
+/// `DailySummaryView` groups summarized [`chrono::Duration`] by local date, time of entries is
+/// attributed to each local date it covers.
 #[derive(Debug)]
 pub struct DailySummaryView(DateDurationMap);
 
+/// Serialized as `[{"date", "seconds"}]`.
+impl Serialize for DailySummaryView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_summary(&self.0, |_| None, serializer)
+    }
+}
+
 impl DailySummaryView {
-    pub fn new(entries: &[FinishedEntry]) -> Self {
-        let mut view: DateDurationMap = Map::new();
-        for entry in entries.iter() {
-            let duration = entry.end - entry.id.start;
-            let start = entry.id.start.with_timezone(&Local).date_naive();
-            view.entry(start)
+    pub fn new(entries: &[FinishedEntry], rounding: Option<Rounding>) -> Self {
+        DailySummaryView(summary_by_date(entries, &Local, rounding, |date| date))
+    }
+}
+
+/// Durations of `entries` by local naive date in `tz` and then by title.
+///
+/// Entries running across midnight count to each of the dates, and the time of each entry on a
+/// date is rounded if `rounding` is per [`RoundScope::Entry`].
+fn durations_by_date<Tz: TimeZone>(
+    entries: &[FinishedEntry],
+    tz: &Tz,
+    rounding: Option<Rounding>,
+) -> Map<NaiveDate, TitleDurationMap> {
+    let per_entry = rounding.filter(|r| r.scope == RoundScope::Entry);
+    let mut view: Map<NaiveDate, TitleDurationMap> = Map::new();
+    for entry in entries.iter() {
+        let mut dates: DateDurationMap = Map::new();
+        for (date, start, end) in daily_spans(entry, tz) {
+            let duration = end - start;
+            dates
+                .entry(date)
+                .and_modify(|dur| *dur = *dur + duration)
+                .or_insert(duration);
+        }
+        for (date, duration) in dates {
+            let duration = per_entry.map_or(duration, |r| r.round(duration));
+            view.entry(date)
+                .or_default()
+                .entry(entry.id.title.to_string())
                 .and_modify(|dur| *dur = *dur + duration)
                 .or_insert(duration);
         }
+    }
+    view
+}
+
+/// Round a total of a title on a date if `rounding` is per [`RoundScope::Day`].
+fn round_day_total(rounding: Option<Rounding>, duration: chrono::Duration) -> chrono::Duration {
+    match rounding {
+        Some(r) if r.scope == RoundScope::Day => r.round(duration),
+        _ => duration,
+    }
+}
+
+/// Sum durations of `entries` by `date_key` of local naive date in `tz`.
+///
+/// Totals are the sums of titles of [`detail_by_date`], so that summaries agree with details
+/// whatever the `rounding` is.
+fn summary_by_date<Tz: TimeZone>(
+    entries: &[FinishedEntry],
+    tz: &Tz,
+    rounding: Option<Rounding>,
+    date_key: impl Fn(NaiveDate) -> NaiveDate,
+) -> DateDurationMap {
+    detail_by_date(entries, tz, rounding, date_key)
+        .into_iter()
+        .map(|(date, titles)| {
+            let total = titles
+                .values()
+                .fold(chrono::Duration::zero(), |acc, d| acc + *d);
+            (date, total)
+        })
+        .collect()
+}
 
-        DailySummaryView(view)
+/// Sum durations of `entries` by title, then by `date_key` of local naive date in `tz`,
+/// see [`durations_by_date`].
+///
+/// Totals of titles on dates are rounded before summed by `date_key` if `rounding` is per
+/// [`RoundScope::Day`].
+fn detail_by_date<Tz: TimeZone>(
+    entries: &[FinishedEntry],
+    tz: &Tz,
+    rounding: Option<Rounding>,
+    date_key: impl Fn(NaiveDate) -> NaiveDate,
+) -> Map<NaiveDate, TitleDurationMap> {
+    let mut view: Map<NaiveDate, TitleDurationMap> = Map::new();
+    for (date, titles) in durations_by_date(entries, tz, rounding) {
+        let durations = view.entry(date_key(date)).or_default();
+        for (title, duration) in titles {
+            let duration = round_day_total(rounding, duration);
+            durations
+                .entry(title)
+                .and_modify(|dur| *dur = *dur + duration)
+                .or_insert(duration);
+        }
     }
+    view
 }
 
 impl fmt::Display for DailySummaryView {
@@ -93,30 +359,20 @@ impl fmt::Display for DailySummaryView {
     }
 }
 
-/// `DailyDetailView` groups `(FinishedEntry::id::title, chrono::Duration)` by local naive date of `FinishedEntry` start.
+/// `DailyDetailView` groups `(FinishedEntry::id::title, chrono::Duration)` by local date, time of
+/// entries is attributed to each local date it covers.
 #[derive(Debug)]
 pub struct DailyDetailView(Map<NaiveDate, TitleDurationMap>);
-impl DailyDetailView {
-    pub fn new(entries: &[FinishedEntry]) -> Self {
-        let mut view: Map<NaiveDate, TitleDurationMap> = Map::new();
-        for entry in entries.iter() {
-            let duration = entry.end - entry.id.start;
-            let start = entry.id.start.with_timezone(&Local).date_naive();
-            view.entry(start)
-                .and_modify(|inner_map| {
-                    inner_map
-                        .entry(entry.id.title.to_string())
-                        .and_modify(|dur| *dur = *dur + duration)
-                        .or_insert(duration);
-                })
-                .or_insert_with(|| {
-                    let mut inner_map: TitleDurationMap = TitleDurationMap::new();
-                    inner_map.insert(entry.id.title.to_string(), duration);
-                    inner_map
-                });
-        }
+/// Serialized as `[{"date", "seconds", "titles": [{"title", "seconds"}]}]`.
+impl Serialize for DailyDetailView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_detail(&self.0, |_| None, serializer)
+    }
+}
 
-        DailyDetailView(view)
+impl DailyDetailView {
+    pub fn new(entries: &[FinishedEntry], rounding: Option<Rounding>) -> Self {
+        DailyDetailView(detail_by_date(entries, &Local, rounding, |date| date))
     }
 }
 
@@ -143,7 +399,701 @@ impl fmt::Display for DailyDetailView {
     }
 }
 
-#[derive(Serialize, Debug, Eq, PartialEq, Clone)]
+/// Grouping period of weekly and monthly views.
+#[derive(Debug, Clone, Copy)]
+enum Period {
+    /// Week starts on the given weekday.
+    Week(Weekday),
+    Month,
+}
+
+impl Period {
+    /// First date of the period which `date` belongs to.
+    fn key(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Week(week_start) => {
+                let days_from_start = (7 + date.weekday().num_days_from_monday()
+                    - week_start.num_days_from_monday())
+                    % 7;
+                date - chrono::Duration::days(days_from_start as i64)
+            }
+            Period::Month => date.with_day(1).unwrap(),
+        }
+    }
+
+    /// Display label of the period starts on `key`.
+    fn label(&self, key: &NaiveDate) -> String {
+        match self {
+            Period::Week(Weekday::Mon) => {
+                let iso_week = key.iso_week();
+                format!(
+                    "{}-W{:0>2} ({} ~ {})",
+                    iso_week.year(),
+                    iso_week.week(),
+                    key,
+                    *key + chrono::Duration::days(6)
+                )
+            }
+            Period::Week(_) => format!("{} ~ {}", key, *key + chrono::Duration::days(6)),
+            Period::Month => key.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Summarized [`chrono::Duration`] grouped by [`Period`].
+#[derive(Debug)]
+struct PeriodSummary {
+    period: Period,
+    durations: DateDurationMap,
+}
+
+impl PeriodSummary {
+    fn new(entries: &[FinishedEntry], period: Period, rounding: Option<Rounding>) -> Self {
+        PeriodSummary {
+            period,
+            durations: summary_by_date(entries, &Local, rounding, |date| period.key(date)),
+        }
+    }
+}
+
+/// Serialized as `[{"period", "date", "seconds"}]`, `date` is the first day of the period.
+impl Serialize for PeriodSummary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_summary(
+            &self.durations,
+            |key| Some(self.period.label(key)),
+            serializer,
+        )
+    }
+}
+
+impl fmt::Display for PeriodSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut r: fmt::Result = Ok(());
+        let mut total = chrono::Duration::days(0);
+        for (key, duration) in self.durations.iter() {
+            r = r.and_then(|_| {
+                writeln!(
+                    f,
+                    "{}: {}",
+                    self.period.label(key),
+                    strify_duration(duration)
+                )
+            });
+            total = total + *duration;
+        }
+
+        if self.durations.len() > 1 {
+            r = r.and_then(|_| writeln!(f, "(Total): {}", strify_duration(&total)));
+        }
+        r
+    }
+}
+
+/// `(title, chrono::Duration)` grouped by [`Period`].
+#[derive(Debug)]
+struct PeriodDetail {
+    period: Period,
+    durations: Map<NaiveDate, TitleDurationMap>,
+}
+
+impl PeriodDetail {
+    fn new(entries: &[FinishedEntry], period: Period, rounding: Option<Rounding>) -> Self {
+        PeriodDetail {
+            period,
+            durations: detail_by_date(entries, &Local, rounding, |date| period.key(date)),
+        }
+    }
+}
+
+/// Serialized as `[{"period", "date", "seconds", "titles": [{"title", "seconds"}]}]`,
+/// `date` is the first day of the period.
+impl Serialize for PeriodDetail {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_detail(
+            &self.durations,
+            |key| Some(self.period.label(key)),
+            serializer,
+        )
+    }
+}
+
+impl fmt::Display for PeriodDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut r: fmt::Result = Ok(());
+        let mut total_duration = chrono::Duration::days(0);
+        for (key, detail) in self.durations.iter() {
+            r = r.and_then(|_| writeln!(f, "{}: ", self.period.label(key)));
+
+            let mut period_total = chrono::Duration::days(0);
+            for (title, duration) in detail.iter() {
+                r = r.and_then(|_| writeln!(f, "\t{title}: {}", strify_duration(duration)));
+                period_total = period_total + *duration;
+            }
+
+            r = r.and_then(|_| writeln!(f, "\t(Total): {}\n", strify_duration(&period_total)));
+            total_duration = total_duration + period_total;
+        }
+        if self.durations.len() > 1 {
+            r = r.and_then(|_| writeln!(f, "(Total): {}", strify_duration(&total_duration)));
+        }
+        r
+    }
+}
+
+/// `WeeklySummaryView` groups summarized [`chrono::Duration`] by week of the local dates covered by
+/// entries.
+#[derive(Serialize, Debug)]
+pub struct WeeklySummaryView(PeriodSummary);
+
+impl WeeklySummaryView {
+    /// Weeks start on `week_start`, weeks starting on Monday are labeled by ISO week.
+    pub fn new(entries: &[FinishedEntry], week_start: Weekday, rounding: Option<Rounding>) -> Self {
+        WeeklySummaryView(PeriodSummary::new(
+            entries,
+            Period::Week(week_start),
+            rounding,
+        ))
+    }
+}
+
+impl fmt::Display for WeeklySummaryView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// `WeeklyDetailView` groups `(FinishedEntry::id::title, chrono::Duration)` by week of the local
+/// dates covered by entries.
+#[derive(Serialize, Debug)]
+pub struct WeeklyDetailView(PeriodDetail);
+
+impl WeeklyDetailView {
+    /// Weeks start on `week_start`, weeks starting on Monday are labeled by ISO week.
+    pub fn new(entries: &[FinishedEntry], week_start: Weekday, rounding: Option<Rounding>) -> Self {
+        WeeklyDetailView(PeriodDetail::new(
+            entries,
+            Period::Week(week_start),
+            rounding,
+        ))
+    }
+}
+
+impl fmt::Display for WeeklyDetailView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// `MonthlySummaryView` groups summarized [`chrono::Duration`] by calendar month of the local dates
+/// covered by entries.
+#[derive(Serialize, Debug)]
+pub struct MonthlySummaryView(PeriodSummary);
+
+impl MonthlySummaryView {
+    pub fn new(entries: &[FinishedEntry], rounding: Option<Rounding>) -> Self {
+        MonthlySummaryView(PeriodSummary::new(entries, Period::Month, rounding))
+    }
+}
+
+impl fmt::Display for MonthlySummaryView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// `MonthlyDetailView` groups `(FinishedEntry::id::title, chrono::Duration)` by calendar month of
+/// the local dates covered by entries.
+#[derive(Serialize, Debug)]
+pub struct MonthlyDetailView(PeriodDetail);
+
+impl MonthlyDetailView {
+    pub fn new(entries: &[FinishedEntry], rounding: Option<Rounding>) -> Self {
+        MonthlyDetailView(PeriodDetail::new(entries, Period::Month, rounding))
+    }
+}
+
+impl fmt::Display for MonthlyDetailView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// `TagSummaryView` groups summarized [`chrono::Duration`] by tags of [`FinishedEntry`].
+///
+/// An entry with multiple tags counts to each of them, entries without tags are grouped as `<untagged>`.
+#[derive(Debug)]
+pub struct TagSummaryView(TitleDurationMap);
+
+/// Serialized as `[{"tag", "seconds"}]`.
+impl Serialize for TagSummaryView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct TagSeconds<'v> {
+            tag: &'v str,
+            seconds: i64,
+        }
+
+        serializer.collect_seq(self.0.iter().map(|(tag, d)| TagSeconds {
+            tag,
+            seconds: d.num_seconds(),
+        }))
+    }
+}
+
+impl TagSummaryView {
+    pub fn new(entries: &[FinishedEntry]) -> Self {
+        let untagged = ["<untagged>".to_string()];
+        let mut view: TitleDurationMap = Map::new();
+        for entry in entries.iter() {
+            let duration = entry.active_duration();
+            let tags = if entry.tags.is_empty() {
+                &untagged[..]
+            } else {
+                &entry.tags[..]
+            };
+            for tag in tags.iter() {
+                view.entry(tag.clone())
+                    .and_modify(|dur| *dur = *dur + duration)
+                    .or_insert(duration);
+            }
+        }
+
+        TagSummaryView(view)
+    }
+}
+
+impl fmt::Display for TagSummaryView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut r: fmt::Result = Ok(());
+        for (tag, duration) in self.0.iter() {
+            r = r.and_then(|_| writeln!(f, "{tag}: {}", strify_duration(duration)));
+        }
+        r
+    }
+}
+
+/// Total duration of a project, including durations of its sub projects.
+#[derive(Debug)]
+struct ProjectNode {
+    total: chrono::Duration,
+    children: Map<String, ProjectNode>,
+}
+
+impl Default for ProjectNode {
+    fn default() -> Self {
+        ProjectNode {
+            total: chrono::Duration::zero(),
+            children: Map::new(),
+        }
+    }
+}
+
+impl ProjectNode {
+    fn add<'p>(&mut self, mut path: impl Iterator<Item = &'p str>, duration: chrono::Duration) {
+        self.total = self.total + duration;
+        if let Some(name) = path.next() {
+            self.children
+                .entry(name.to_string())
+                .or_default()
+                .add(path, duration);
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn fmt_children(&self, f: &mut fmt::Formatter<'_>, level: usize) -> fmt::Result {
+        self.children.iter().try_for_each(|(name, node)| {
+            writeln!(
+                f,
+                "{}{name}: {}",
+                "\t".repeat(level),
+                strify_duration(&node.total)
+            )
+            .and_then(|_| node.fmt_children(f, level + 1))
+        })
+    }
+}
+
+/// Serialized as children `[{"name", "seconds", "children"}]`, `children` is omitted for leaves.
+impl Serialize for ProjectNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Project<'v> {
+            name: &'v str,
+            seconds: i64,
+            #[serde(skip_serializing_if = "ProjectNode::is_leaf")]
+            children: &'v ProjectNode,
+        }
+
+        serializer.collect_seq(self.children.iter().map(|(name, node)| Project {
+            name,
+            seconds: node.total.num_seconds(),
+            children: node,
+        }))
+    }
+}
+
+/// `ProjectTreeView` sums [`chrono::Duration`] of [`FinishedEntry`] by projects of titles, which
+/// are separated by [`PROJECT_SEPARATOR`], so `clocking/server` and `clocking/views` roll up to
+/// `clocking`.
+#[derive(Serialize, Debug)]
+pub struct ProjectTreeView(ProjectNode);
+
+impl ProjectTreeView {
+    /// Levels deeper than `depth` are collapsed into their parents, all levels are kept if `None`.
+    ///
+    /// Titles of separators only, like `/`, are counted under the raw title.
+    pub fn new(entries: &[FinishedEntry], depth: Option<usize>) -> Self {
+        let mut root = ProjectNode::default();
+        for entry in entries.iter() {
+            let title = entry.id.title.as_ref();
+            let mut path = project_path(title).peekable();
+            if path.peek().is_none() {
+                root.add(std::iter::once(title), entry.active_duration());
+            } else {
+                root.add(
+                    path.take(depth.unwrap_or(usize::MAX)),
+                    entry.active_duration(),
+                );
+            }
+        }
+
+        ProjectTreeView(root)
+    }
+}
+
+impl fmt::Display for ProjectTreeView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_children(f, 0)?;
+        if self.0.children.len() > 1 {
+            writeln!(f, "(Total): {}", strify_duration(&self.0.total))?;
+        }
+        Ok(())
+    }
+}
+
+/// Billed time of a title at its rate.
+#[derive(Debug)]
+struct BillingItem {
+    duration: chrono::Duration,
+    cents_per_hour: i64,
+}
+
+impl BillingItem {
+    /// Whole minutes billed, durations are rounded to the nearest minute.
+    fn minutes(&self) -> i64 {
+        (self.duration.num_seconds() + 30) / 60
+    }
+
+    /// Amount in cents rounded to the nearest cent.
+    fn cents(&self) -> i64 {
+        (self.minutes() * self.cents_per_hour + 30) / 60
+    }
+}
+
+/// Format `minutes` as hours like `27:30`, which are not carried into days unlike [`strify_duration`].
+fn strify_hours(minutes: i64) -> String {
+    format!("{}:{:0>2}", minutes / 60, minutes % 60)
+}
+
+/// `BillingView` groups billed time and amounts of [`FinishedEntry`] by client and then by title,
+/// rates are found by [`Rate::find`].
+///
+/// Time of titles without a rate is listed as unbilled.
+#[derive(Debug)]
+pub struct BillingView {
+    clients: Map<String, Map<String, BillingItem>>,
+    unbilled: TitleDurationMap,
+}
+
+/// Serialized as `{"clients": [{"client", "minutes", "cents", "items": [{"title", "minutes",
+/// "cents_per_hour", "cents"}]}], "unbilled": [{"title", "seconds"}]}`, amounts are in cents.
+impl Serialize for BillingView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Item<'v> {
+            title: &'v str,
+            minutes: i64,
+            cents_per_hour: i64,
+            cents: i64,
+        }
+        #[derive(Serialize)]
+        struct Client<'v> {
+            client: &'v str,
+            minutes: i64,
+            cents: i64,
+            items: Vec<Item<'v>>,
+        }
+        #[derive(Serialize)]
+        struct Billing<'v> {
+            clients: Vec<Client<'v>>,
+            unbilled: Vec<TitleSeconds<'v>>,
+        }
+
+        Billing {
+            clients: self
+                .clients
+                .iter()
+                .map(|(client, items)| Client {
+                    client,
+                    minutes: items.values().map(BillingItem::minutes).sum(),
+                    cents: items.values().map(BillingItem::cents).sum(),
+                    items: items
+                        .iter()
+                        .map(|(title, item)| Item {
+                            title,
+                            minutes: item.minutes(),
+                            cents_per_hour: item.cents_per_hour,
+                            cents: item.cents(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            unbilled: self
+                .unbilled
+                .iter()
+                .map(|(title, d)| TitleSeconds {
+                    title,
+                    seconds: d.num_seconds(),
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl BillingView {
+    /// Durations are rounded the same way as [`DailyDetailView`] and summed over dates.
+    pub fn new(entries: &[FinishedEntry], rates: &[Rate], rounding: Option<Rounding>) -> Self {
+        let mut clients: Map<String, Map<String, BillingItem>> = Map::new();
+        let mut unbilled: TitleDurationMap = Map::new();
+        for (title, duration) in title_totals(entries, &Local, rounding) {
+            match Rate::find(rates, &title) {
+                Some(rate) => {
+                    clients.entry(rate.client.clone()).or_default().insert(
+                        title,
+                        BillingItem {
+                            duration,
+                            cents_per_hour: rate.cents_per_hour,
+                        },
+                    );
+                }
+                None => {
+                    unbilled.insert(title, duration);
+                }
+            }
+        }
+
+        BillingView { clients, unbilled }
+    }
+
+    /// Keep billed time of `client` only, unbilled time is dropped too.
+    pub fn only_client(mut self, client: &str) -> Self {
+        self.clients.retain(|name, _| name == client);
+        self.unbilled.clear();
+        self
+    }
+
+    /// Markdown invoice body of `client` for the period `from` ~ `to`, None if nothing is billed.
+    pub fn invoice(&self, client: &str, from: NaiveDate, to: NaiveDate) -> Option<String> {
+        let items = self.clients.get(client)?;
+        // titles and clients are literal, not markdown or html of the invoice and its html
+        let escape = |text: &str| {
+            text.chars().fold(String::new(), |mut escaped, c| {
+                if "\\`*_[]<>|#!&~".contains(c) {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+                escaped
+            })
+        };
+        let mut text = format!(
+            "# Invoice for {}\n\nPeriod: {from} ~ {to}\n\n| Item | Time | Rate | Amount |\n| --- | ---: | ---: | ---: |\n",
+            escape(client)
+        );
+        for (title, item) in items.iter() {
+            text.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                escape(title),
+                strify_hours(item.minutes()),
+                strify_cents(item.cents_per_hour),
+                strify_cents(item.cents())
+            ));
+        }
+        text.push_str(&format!(
+            "| **Total** | **{}** | | **{}** |\n",
+            strify_hours(items.values().map(BillingItem::minutes).sum()),
+            strify_cents(items.values().map(BillingItem::cents).sum())
+        ));
+        Some(text)
+    }
+
+    /// Html of [`BillingView::invoice`].
+    #[cfg(feature = "http")]
+    pub fn invoice_html(&self, client: &str, from: NaiveDate, to: NaiveDate) -> Option<String> {
+        self.invoice(client, from, to)
+            .map(|text| render_markdown(&text, pulldown_cmark::Options::ENABLE_TABLES))
+    }
+}
+
+impl fmt::Display for BillingView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (client, items) in self.clients.iter() {
+            writeln!(f, "{client}:")?;
+            for (title, item) in items.iter() {
+                writeln!(
+                    f,
+                    "\t{title}: {} x {}/h = {}",
+                    strify_hours(item.minutes()),
+                    strify_cents(item.cents_per_hour),
+                    strify_cents(item.cents())
+                )?;
+            }
+            writeln!(
+                f,
+                "\t(Total): {}",
+                strify_cents(items.values().map(BillingItem::cents).sum())
+            )?;
+        }
+        if !self.unbilled.is_empty() {
+            writeln!(f, "(No rate):")?;
+            for (title, duration) in self.unbilled.iter() {
+                writeln!(f, "\t{title}: {}", strify_duration(duration))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Time spent in the current period of a goal.
+#[derive(Debug)]
+struct GoalProgress {
+    goal: Goal,
+    spent: chrono::Duration,
+}
+
+impl GoalProgress {
+    /// Time left to reach the target, or before exceeding the cap, negative once exceeded.
+    fn remaining(&self) -> chrono::Duration {
+        self.goal.duration - self.spent
+    }
+
+    fn cap_exceeded(&self) -> bool {
+        self.goal.kind == GoalKind::Cap && self.spent > self.goal.duration
+    }
+}
+
+/// `GoalProgressView` shows time spent of each [`Goal`] in the current day or week, with the time
+/// remaining.
+#[derive(Debug)]
+pub struct GoalProgressView(Vec<GoalProgress>);
+
+/// Serialized as `[{"subject", "period", "kind", "seconds", "spent_seconds", "remaining_seconds"}]`,
+/// `remaining_seconds` is negative if exceeded.
+impl Serialize for GoalProgressView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Progress<'v> {
+            #[serde(flatten)]
+            goal: &'v Goal,
+            spent_seconds: i64,
+            remaining_seconds: i64,
+        }
+
+        serializer.collect_seq(self.0.iter().map(|progress| Progress {
+            goal: &progress.goal,
+            spent_seconds: progress.spent.num_seconds(),
+            remaining_seconds: progress.remaining().num_seconds(),
+        }))
+    }
+}
+
+impl GoalProgressView {
+    /// Progress of `goals` on local date `today` and in the week of it starting on `week_start`.
+    ///
+    /// `entries` should cover the week, ongoing entries count by [`UnfinishedEntry::finished_at`].
+    pub fn new(
+        goals: &[Goal],
+        entries: &[FinishedEntry],
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> Self {
+        Self::new_in(goals, entries, today, week_start, &Local)
+    }
+
+    fn new_in<Tz: TimeZone>(
+        goals: &[Goal],
+        entries: &[FinishedEntry],
+        today: NaiveDate,
+        week_start: Weekday,
+        tz: &Tz,
+    ) -> Self {
+        let progress = goals
+            .iter()
+            .map(|goal| {
+                let since = match goal.period {
+                    GoalPeriod::Day => today,
+                    GoalPeriod::Week => Period::Week(week_start).key(today),
+                };
+                let spent = entries
+                    .iter()
+                    .filter(|entry| goal.subject.matches(&entry.id.title, &entry.tags))
+                    .flat_map(|entry| daily_spans(entry, tz))
+                    .filter(|(date, _, _)| since <= *date && *date <= today)
+                    .fold(chrono::Duration::zero(), |acc, (_, start, end)| {
+                        acc + (end - start)
+                    });
+                GoalProgress {
+                    goal: goal.clone(),
+                    spent,
+                }
+            })
+            .collect();
+
+        GoalProgressView(progress)
+    }
+
+    /// Caps exceeded already by entries of `title` with `tags`, along with the time spent.
+    pub fn exceeded_caps(&self, title: &str, tags: &[String]) -> Vec<(&Goal, chrono::Duration)> {
+        self.0
+            .iter()
+            .filter(|progress| progress.cap_exceeded())
+            .filter(|progress| progress.goal.subject.matches(title, tags))
+            .map(|progress| (&progress.goal, progress.spent))
+            .collect()
+    }
+}
+
+impl fmt::Display for GoalProgressView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for progress in self.0.iter() {
+            let goal = &progress.goal;
+            let remaining = progress.remaining();
+            let state = match goal.kind {
+                GoalKind::Target if remaining <= chrono::Duration::zero() => "reached".to_string(),
+                GoalKind::Cap if progress.cap_exceeded() => {
+                    format!("exceeded by {}", strify_duration(&-remaining))
+                }
+                _ => format!("{} remaining", strify_duration(&remaining)),
+            };
+            writeln!(
+                f,
+                "{}: {} of {} {} a {}, {state}",
+                goal.subject,
+                strify_duration(&progress.spent),
+                strify_duration(&goal.duration),
+                goal.kind,
+                goal.period
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
 struct TimeSpanWithTitle(TimeSpan, String);
 impl Ord for TimeSpanWithTitle {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -153,87 +1103,100 @@ impl Ord for TimeSpanWithTitle {
 
 impl PartialOrd for TimeSpanWithTitle {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.0.cmp(&other.0))
+        Some(self.cmp(other))
     }
 }
 
-/// `DailyDistributionView` groups sorted `Vec<Effort>` by local naive date of `FinishedEntry` start.
+/// `DailyDistributionView` groups sorted `Vec<Effort>` by local date, entries crossing midnight are
+/// split into an effort on each local date they cover.
 #[derive(Debug)]
 pub struct DailyDistributionView(Map<NaiveDate, Vec<TimeSpanWithTitle>>);
+/// Serialized as `[{"date", "spans": [{"title", "start", "end", "seconds"}]}]`, idle spans are titled `<idle>`.
+impl Serialize for DailyDistributionView {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Span<'v> {
+            title: &'v str,
+            start: DateTime<FixedOffset>,
+            end: DateTime<FixedOffset>,
+            seconds: i64,
+        }
+        #[derive(Serialize)]
+        struct Day<'v> {
+            date: &'v NaiveDate,
+            spans: Vec<Span<'v>>,
+        }
+
+        serializer.collect_seq(self.0.iter().map(|(date, spans)| {
+            Day {
+                date,
+                spans: spans
+                    .iter()
+                    .map(|span| Span {
+                        title: &span.1,
+                        start: span.0.start(),
+                        end: span.0.end(),
+                        seconds: span.0.duration().num_seconds(),
+                    })
+                    .collect(),
+            }
+        }))
+    }
+}
+
 impl DailyDistributionView {
-    pub fn new(entries: &[FinishedEntry]) -> Self {
-        let mut view: Map<NaiveDate, Vec<TimeSpanWithTitle>> = Map::new();
+    /// Idle spans fill gaps between entries, and the rest of `workday` hours of each date.
+    pub fn new(entries: &[FinishedEntry], workday: &Workday) -> Self {
+        Self::new_in(entries, workday, &Local)
+    }
+
+    fn new_in<Tz: TimeZone>(entries: &[FinishedEntry], workday: &Workday, tz: &Tz) -> Self {
+        let mut view: Map<NaiveDate, Vec<TitledSpan>> = Map::new();
         for entry in entries.iter() {
-            let start_date = entry.id.start.with_timezone(&Local).date_naive();
-            view.entry(start_date)
-                .and_modify(|efforts| {
-                    efforts.push(TimeSpanWithTitle(
-                        // TODO: handle invalid timespan (bad data in database)
-                        TimeSpan::build(
-                            entry.id.start.with_timezone(&Local),
-                            entry.end.with_timezone(&Local),
-                        )
-                        .unwrap(),
-                        entry.id.title.to_string(),
-                    ));
-                })
-                .or_insert_with(|| {
-                    // TODO: handle invalid timespan (bad data in database)
-                    vec![TimeSpanWithTitle(
-                        TimeSpan::build(
-                            entry.id.start.with_timezone(&Local),
-                            entry.end.with_timezone(&Local),
-                        )
-                        .unwrap(),
-                        entry.id.title.to_string(),
-                    )]
-                });
-        }
-
-        let today_naive = Local::now().date_naive();
-        let local_fixed_offset = Local.offset_from_local_date(&today_naive).unwrap();
-
-        let day_start_time = chrono::naive::NaiveTime::from_hms_opt(8, 0, 0).unwrap();
-        let day_end_time = chrono::naive::NaiveTime::from_hms_opt(21, 0, 0).unwrap();
-
-        let idle_title = "<idle>".to_string();
+            // entries ending before start have no active spans, `clocking check` reports them,
+            // pauses are shown as idle
+            for (date, start, end) in daily_spans(entry, tz) {
+                view.entry(date)
+                    .or_default()
+                    .push((start, end, entry.id.title.to_string()));
+            }
+        }
+
+        let span = |start: DateTime<Utc>, end: DateTime<Utc>, title: &str| {
+            TimeSpan::build(to_fixed(&start, tz), to_fixed(&end, tz))
+                .ok()
+                .map(|span| TimeSpanWithTitle(span, title.to_string()))
+        };
+        let idle_title = "<idle>";
         let view = view
-            .iter_mut()
-            .map(|(date, efforts)| {
-                efforts.sort();
-                let mut current_dt = date.and_time(day_start_time);
+            .into_iter()
+            .map(|(date, mut spans)| {
+                spans.sort_by_key(|(start, _, _)| *start);
+                // on days off idle spans are only between entries
+                let hours = workday.hours(date.weekday());
+                let mut current = match hours {
+                    Some((day_start_time, _)) => local_to_utc(&date.and_time(day_start_time), tz),
+                    None => spans[0].0,
+                };
                 let mut with_idles_sorted: Vec<TimeSpanWithTitle> = vec![];
-                for eff in efforts.iter() {
-                    if current_dt < eff.0.start().naive_local() {
-                        // TODO: handle invalid timespan (bad data in database)
-                        with_idles_sorted.push(TimeSpanWithTitle(
-                            TimeSpan::build(
-                                DateTime::from_local(current_dt, local_fixed_offset),
-                                eff.0.start(),
-                            )
-                            .unwrap(),
-                            idle_title.clone(),
-                        ));
-                        current_dt = eff.0.end().naive_local();
+                for (start, end, title) in spans.iter() {
+                    if current < *start {
+                        with_idles_sorted.extend(span(current, *start, idle_title));
                     }
+                    // an entry overlapped by a previous one must not move idle start backwards
+                    current = current.max(*end);
 
-                    with_idles_sorted.push(eff.clone());
+                    with_idles_sorted.extend(span(*start, *end, title));
                 }
 
-                let day_end_dt = date.and_time(day_end_time);
-                if current_dt < day_end_dt {
-                    // TODO: handle invalid timespan (bad data in database)
-                    with_idles_sorted.push(TimeSpanWithTitle(
-                        TimeSpan::build(
-                            DateTime::from_local(current_dt, local_fixed_offset),
-                            DateTime::from_local(day_end_dt, local_fixed_offset),
-                        )
-                        .unwrap(),
-                        idle_title.clone(),
-                    ));
+                if let Some((_, day_end_time)) = hours {
+                    let day_end = local_to_utc(&date.and_time(day_end_time), tz);
+                    if current < day_end {
+                        with_idles_sorted.extend(span(current, day_end, idle_title));
+                    }
                 }
 
-                (*date, with_idles_sorted)
+                (date, with_idles_sorted)
             })
             .collect();
 
@@ -259,3 +1222,526 @@ impl fmt::Display for DailyDistributionView {
         r
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    fn entry(title: &str, start: (u32, u32), end: (u32, u32)) -> FinishedEntry<'static> {
+        let date = Local::now().date_naive();
+        let at = |(h, m)| {
+            Local
+                .from_local_datetime(&date.and_hms_opt(h, m, 0).unwrap())
+                .earliest()
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        entry_between(title, &[], at(start), at(end))
+    }
+
+    fn entry_between(
+        title: &str,
+        tags: &[&str],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> FinishedEntry<'static> {
+        FinishedEntry {
+            id: EntryId {
+                title: Cow::Owned(title.to_string()),
+                start,
+            },
+            end,
+            notes: "".into(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            pauses: vec![],
+        }
+    }
+
+    /// `h` o'clock of 2023-`m`-`d` in [`crate::test_tz::Cet`].
+    fn cet(m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        let date = NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+        local_to_utc(&date.and_hms_opt(h, 0, 0).unwrap(), &crate::test_tz::Cet)
+    }
+
+    #[test]
+    fn distribution_with_bad_entries() {
+        let entries = vec![
+            entry("Early", (7, 0), (9, 0)),
+            entry("Overlapped", (8, 30), (9, 30)),
+            entry("Inverted", (11, 0), (10, 0)),
+            entry("Late", (10, 0), (11, 0)),
+        ];
+        let view = DailyDistributionView::new(&entries, &Workday::default());
+        let spans = view.0.values().next().unwrap();
+        let titles: Vec<&str> = spans.iter().map(|s| s.1.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Early", "Overlapped", "<idle>", "Late", "<idle>"]
+        );
+        assert_eq!(spans[2].0.duration(), chrono::Duration::minutes(30));
+
+        let view = EntryDetailView::new(&entries, None);
+        assert!(!view.spans.contains_key("Inverted"));
+    }
+
+    #[test]
+    fn pauses_not_counted() {
+        let mut paused = entry("Paused", (9, 0), (12, 0));
+        paused.pauses = vec![Pause {
+            start: paused.id.start + chrono::Duration::hours(1),
+            end: Some(paused.id.start + chrono::Duration::minutes(90)),
+        }];
+        let entries = vec![paused];
+
+        let view = DailySummaryView::new(&entries, None);
+        assert_eq!(
+            view.0.values().collect::<Vec<_>>(),
+            vec![&chrono::Duration::minutes(150)]
+        );
+        let view = EntryDetailView::new(&entries, None);
+        assert_eq!(view.spans["Paused"].len(), 2);
+
+        let view = DailyDistributionView::new(&entries, &Workday::default());
+        let spans = view.0.values().next().unwrap();
+        let titles: Vec<&str> = spans.iter().map(|s| s.1.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["<idle>", "Paused", "<idle>", "Paused", "<idle>"]
+        );
+        assert_eq!(spans[2].0.duration(), chrono::Duration::minutes(30));
+    }
+
+    #[test]
+    fn overlaps_split() {
+        let entries = vec![
+            entry("Meeting", (9, 0), (10, 0)),
+            entry("Coding", (9, 30), (11, 0)),
+            entry("Inverted", (11, 0), (10, 0)),
+        ];
+        let view = EntryDetailView::new(&entries, None);
+        assert_eq!(
+            view.spans["Meeting"][0].duration() + view.spans["Coding"][0].duration(),
+            chrono::Duration::minutes(150),
+            "overlapping time is counted twice by default"
+        );
+
+        let split = split_overlaps(&entries);
+        assert_eq!(split[0].active_duration(), chrono::Duration::minutes(45));
+        assert_eq!(split[1].active_duration(), chrono::Duration::minutes(75));
+        assert_eq!(split[2].active_duration(), chrono::Duration::zero());
+        let view = DailySummaryView::new(&split, None);
+        assert_eq!(
+            view.0.values().collect::<Vec<_>>(),
+            vec![&chrono::Duration::minutes(120)]
+        );
+        let view = DailyDistributionView::new(&split, &Workday::default());
+        let spans = view.0.values().next().unwrap();
+        let titles: Vec<&str> = spans.iter().map(|s| s.1.as_str()).collect();
+        assert_eq!(titles, vec!["<idle>", "Meeting", "Coding", "<idle>"]);
+        assert_eq!(spans[1].0.duration(), chrono::Duration::minutes(45));
+    }
+
+    #[test]
+    fn workday_hours() {
+        let entries = vec![
+            entry("Early", (7, 0), (9, 0)),
+            entry("Late", (10, 0), (11, 0)),
+        ];
+        let weekday = Local::now().weekday();
+        let titles = |workday: &Workday| {
+            let view = DailyDistributionView::new(&entries, workday);
+            let spans = view.0.values().next().unwrap();
+            spans
+                .iter()
+                .map(|s| (s.1.clone(), s.0.duration().num_minutes()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut workday = Workday::default();
+        workday
+            .set_hours(weekday, Workday::parse_hours("06:00-12:00").unwrap())
+            .unwrap();
+        assert_eq!(
+            titles(&workday),
+            vec![
+                ("<idle>".to_string(), 60),
+                ("Early".to_string(), 120),
+                ("<idle>".to_string(), 60),
+                ("Late".to_string(), 60),
+                ("<idle>".to_string(), 60),
+            ]
+        );
+
+        workday.set_hours(weekday, None).unwrap();
+        assert_eq!(
+            titles(&workday),
+            vec![
+                ("Early".to_string(), 120),
+                ("<idle>".to_string(), 60),
+                ("Late".to_string(), 60),
+            ]
+        );
+
+        let workday: Workday = "08:00-17:00,off,off,off,off,off,off".parse().unwrap();
+        let workday = workday
+            .with_bounds(None, Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()))
+            .unwrap();
+        assert_eq!(
+            workday.hours(Weekday::Mon),
+            Workday::parse_hours("08:00-12:00").unwrap()
+        );
+        assert_eq!(workday.hours(Weekday::Tue), None, "days off are kept off");
+        assert_eq!(workday.to_string().parse::<Workday>().unwrap(), workday);
+        assert!("09:00-08:00,off,off,off,off,off,off"
+            .parse::<Workday>()
+            .is_err());
+    }
+
+    #[test]
+    fn period_keys() {
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        // 2026-10-16 is a Friday
+        let friday = date("2026-10-16");
+        assert_eq!(Period::Week(Weekday::Mon).key(friday), date("2026-10-12"));
+        assert_eq!(Period::Week(Weekday::Sun).key(friday), date("2026-10-11"));
+        assert_eq!(Period::Week(Weekday::Fri).key(friday), friday);
+        assert_eq!(Period::Week(Weekday::Sat).key(friday), date("2026-10-10"));
+        assert_eq!(Period::Month.key(friday), date("2026-10-01"));
+
+        assert_eq!(
+            Period::Week(Weekday::Mon).label(&date("2026-10-12")),
+            "2026-W42 (2026-10-12 ~ 2026-10-18)"
+        );
+        assert_eq!(
+            Period::Week(Weekday::Sun).label(&date("2026-10-11")),
+            "2026-10-11 ~ 2026-10-17"
+        );
+        assert_eq!(Period::Month.label(&date("2026-10-01")), "2026-10");
+    }
+
+    #[test]
+    fn json_shapes() {
+        let entries = vec![
+            entry("Coding", (9, 0), (10, 0)),
+            entry("Coding", (10, 30), (11, 0)),
+        ];
+        let date = entries[0].id.start.with_timezone(&Local).date_naive();
+
+        let json = serde_json::to_value(DailySummaryView::new(&entries, None)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"date": date.to_string(), "seconds": 5400}])
+        );
+
+        let json = serde_json::to_value(DailyDetailView::new(&entries, None)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "date": date.to_string(),
+                "seconds": 5400,
+                "titles": [{"title": "Coding", "seconds": 5400}],
+            }])
+        );
+
+        let json = serde_json::to_value(DailyDistributionView::new(&entries, &Workday::default()))
+            .unwrap();
+        let spans = json[0]["spans"].as_array().unwrap();
+        assert_eq!(json[0]["date"], date.to_string());
+        assert_eq!(spans[1]["title"], "Coding");
+        assert_eq!(spans[1]["seconds"], 3600);
+        assert_eq!(spans[2]["title"], "<idle>");
+    }
+
+    #[test]
+    fn split_at_local_midnight_across_dst() {
+        use crate::test_tz::Cet;
+        let spanning = |start, end| entry_between("Night", &[], start, end);
+        let date = |m, d| NaiveDate::from_ymd_opt(2023, m, d).unwrap();
+        let hours = |h| chrono::Duration::hours(h);
+
+        // the night loses an hour in spring
+        let spring = spanning(cet(3, 25, 22), cet(3, 26, 4));
+        let summary = summary_by_date(std::slice::from_ref(&spring), &Cet, None, |date| date);
+        assert_eq!(
+            summary.into_iter().collect::<Vec<_>>(),
+            vec![(date(3, 25), hours(2)), (date(3, 26), hours(3))]
+        );
+
+        // and gets it back in autumn
+        let autumn = spanning(cet(10, 28, 23), cet(10, 30, 1));
+        let detail = detail_by_date(std::slice::from_ref(&autumn), &Cet, None, |date| date);
+        let nights: Vec<_> = detail.values().map(|titles| titles["Night"]).collect();
+        assert_eq!(nights, vec![hours(1), hours(25), hours(1)]);
+
+        let off: Workday = "off,off,off,off,off,off,off".parse().unwrap();
+        let view = DailyDistributionView::new_in(&[spring.clone(), autumn], &off, &Cet);
+        let spans: Vec<(NaiveDate, String, String)> = view
+            .0
+            .iter()
+            .flat_map(|(date, spans)| {
+                spans
+                    .iter()
+                    .map(|s| (*date, s.0.start().to_rfc3339(), s.0.end().to_rfc3339()))
+            })
+            .collect();
+        let span = |m, d, start: &str, end: &str| (date(m, d), start.to_string(), end.to_string());
+        assert_eq!(
+            spans,
+            vec![
+                span(
+                    3,
+                    25,
+                    "2023-03-25T22:00:00+01:00",
+                    "2023-03-26T00:00:00+01:00"
+                ),
+                span(
+                    3,
+                    26,
+                    "2023-03-26T00:00:00+01:00",
+                    "2023-03-26T04:00:00+02:00"
+                ),
+                span(
+                    10,
+                    28,
+                    "2023-10-28T23:00:00+02:00",
+                    "2023-10-29T00:00:00+02:00"
+                ),
+                span(
+                    10,
+                    29,
+                    "2023-10-29T00:00:00+02:00",
+                    "2023-10-30T00:00:00+01:00"
+                ),
+                span(
+                    10,
+                    30,
+                    "2023-10-30T00:00:00+01:00",
+                    "2023-10-30T01:00:00+01:00"
+                ),
+            ]
+        );
+
+        // workday bounds are local times of each date
+        let view = DailyDistributionView::new_in(&[spring], &Workday::default(), &Cet);
+        let idle = &view.0[&date(3, 26)][1];
+        assert_eq!(idle.1, "<idle>");
+        assert_eq!(idle.0.start().to_rfc3339(), "2023-03-26T08:00:00+02:00");
+        assert_eq!(idle.0.end().to_rfc3339(), "2023-03-26T21:00:00+02:00");
+    }
+
+    #[test]
+    fn project_tree() {
+        let entries = vec![
+            entry("clocking/server", (9, 0), (10, 0)),
+            entry("clocking / views/ tests", (10, 0), (10, 30)),
+            entry("clocking", (11, 0), (11, 15)),
+            entry("Reading", (12, 0), (13, 0)),
+        ];
+        let view = ProjectTreeView::new(&entries, None);
+        assert_eq!(
+            view.to_string(),
+            "Reading: 1:00\nclocking: 1:45\n\tserver: 1:00\n\tviews: 0:30\n\t\ttests: 0:30\n(Total): 2:45\n"
+        );
+        let separators = [
+            entry("/", (14, 0), (14, 30)),
+            entry("Reading", (15, 0), (16, 0)),
+        ];
+        assert_eq!(
+            ProjectTreeView::new(&separators, None).to_string(),
+            "/: 0:30\nReading: 1:00\n(Total): 1:30\n"
+        );
+        assert_eq!(
+            ProjectTreeView::new(&entries, Some(1)).to_string(),
+            "Reading: 1:00\nclocking: 1:45\n(Total): 2:45\n"
+        );
+
+        let json = serde_json::to_value(ProjectTreeView::new(&entries, Some(2))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"name": "Reading", "seconds": 3600},
+                {"name": "clocking", "seconds": 6300, "children": [
+                    {"name": "server", "seconds": 3600},
+                    {"name": "views", "seconds": 1800},
+                ]},
+            ])
+        );
+    }
+
+    #[test]
+    fn billing() {
+        let rate = |project: &str, client: &str, cents_per_hour| Rate {
+            project: project.to_string(),
+            client: client.to_string(),
+            cents_per_hour,
+        };
+        let rates = vec![
+            rate("acme", "Acme", 10000),
+            rate("acme/support", "Acme", 8050),
+            rate("globex", "Globex", 9000),
+        ];
+        let mut entries = vec![
+            entry("acme/website", (9, 0), (11, 20)),
+            entry("acme/website", (13, 0), (13, 0)),
+            entry("acme/support", (11, 30), (12, 30)),
+            entry("globex", (14, 0), (14, 10)),
+            entry("Reading", (15, 0), (16, 0)),
+        ];
+        // 29.5 seconds more are rounded down to the nearest minute
+        entries[1].end += chrono::Duration::milliseconds(29_500);
+
+        let view = BillingView::new(&entries, &rates, None);
+        assert_eq!(
+            view.to_string(),
+            "Acme:\n\tacme/support: 1:00 x 80.50/h = 80.50\n\tacme/website: 2:20 x 100.00/h = 233.33\n\t(Total): 313.83\n\
+             Globex:\n\tglobex: 0:10 x 90.00/h = 15.00\n\t(Total): 15.00\n\
+             (No rate):\n\tReading: 1:00\n"
+        );
+
+        let date = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+        let invoice = view.invoice("Acme", date, date).unwrap();
+        assert!(invoice.ends_with(
+            "| acme/support | 1:00 | 80.50 | 80.50 |\n\
+             | acme/website | 2:20 | 100.00 | 233.33 |\n\
+             | **Total** | **3:20** | | **313.83** |\n"
+        ));
+        assert_eq!(view.invoice("Initech", date, date), None);
+
+        let raw = [rate("<b>raw</b>", "*Raw* & Co", 6000)];
+        let raw_view = BillingView::new(&[entry("<b>raw</b>", (9, 0), (10, 0))], &raw, None);
+        let invoice = raw_view.invoice("*Raw* & Co", date, date).unwrap();
+        assert!(invoice.starts_with("# Invoice for \\*Raw\\* \\& Co\n"));
+        assert!(invoice.contains("| \\<b\\>raw\\</b\\> | 1:00 |"));
+        #[cfg(feature = "http")]
+        {
+            let html = raw_view.invoice_html("*Raw* & Co", date, date).unwrap();
+            assert!(html.starts_with("<h1>Invoice for *Raw* &amp; Co</h1>"));
+            assert!(html.contains("<td>&lt;b&gt;raw&lt;/b&gt;</td>"));
+        }
+
+        let json = serde_json::to_value(view.only_client("Globex")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "clients": [{"client": "Globex", "minutes": 10, "cents": 1500, "items": [
+                    {"title": "globex", "minutes": 10, "cents_per_hour": 9000, "cents": 1500},
+                ]}],
+                "unbilled": [],
+            })
+        );
+    }
+
+    #[test]
+    fn rounding_boundaries() {
+        let secs = chrono::Duration::seconds;
+        let nearest: Rounding = "15m".parse().unwrap();
+        let up: Rounding = "15m-up".parse().unwrap();
+        for (seconds, to_nearest, to_up) in [
+            (0, 0, 0),
+            (1, 0, 900),
+            (449, 0, 900),
+            (450, 900, 900),
+            (900, 900, 900),
+            (901, 900, 1800),
+            (1349, 900, 1800),
+            (1350, 1800, 1800),
+        ] {
+            assert_eq!(nearest.round(secs(seconds)), secs(to_nearest), "{seconds}s");
+            assert_eq!(up.round(secs(seconds)), secs(to_up), "{seconds}s");
+        }
+        let six: Rounding = "6m-nearest".parse().unwrap();
+        assert_eq!(six.round(secs(179)), secs(0));
+        assert_eq!(six.round(secs(180)), secs(360));
+        assert_eq!(six.to_string(), "6m-nearest per entry");
+
+        for invalid in ["15", "0m", "15m-down", "m-up", "-15m"] {
+            assert!(invalid.parse::<Rounding>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn rounded_views() {
+        let entries = vec![
+            entry("Coding", (9, 0), (9, 5)),
+            entry("Coding", (10, 0), (10, 5)),
+            entry("Review", (11, 0), (11, 20)),
+        ];
+        let minutes = |m| chrono::Duration::minutes(m);
+        let per = |rounding: &str, scope| Rounding {
+            scope,
+            ..rounding.parse().unwrap()
+        };
+
+        // each entry is rounded up before summing
+        let rounding = Some(per("15m-up", RoundScope::Entry));
+        let detail = DailyDetailView::new(&entries, rounding);
+        let titles = detail.0.values().next().unwrap();
+        assert_eq!(titles["Coding"], minutes(30));
+        assert_eq!(titles["Review"], minutes(30));
+        let summary = DailySummaryView::new(&entries, rounding);
+        assert_eq!(summary.0.values().next(), Some(&minutes(60)));
+        let entry_detail = EntryDetailView::new(&entries, rounding);
+        assert_eq!(entry_detail.totals["Coding"], minutes(30));
+        assert_eq!(entry_detail.spans["Coding"].len(), 2);
+
+        // totals of titles on each day are rounded instead, and summed up for the day
+        let rounding = Some(per("15m-up", RoundScope::Day));
+        let detail = DailyDetailView::new(&entries, rounding);
+        let titles = detail.0.values().next().unwrap();
+        assert_eq!(titles["Coding"], minutes(15));
+        assert_eq!(titles["Review"], minutes(30));
+        let summary = DailySummaryView::new(&entries, rounding);
+        assert_eq!(summary.0.values().next(), Some(&minutes(45)));
+        let entry_detail = EntryDetailView::new(&entries, rounding);
+        assert_eq!(entry_detail.totals["Coding"], minutes(15));
+
+        let rounding = Some(per("6m", RoundScope::Day));
+        let summary = DailySummaryView::new(&entries, rounding);
+        assert_eq!(summary.0.values().next(), Some(&minutes(30)));
+        let detail = DailyDetailView::new(&entries, rounding);
+        let titles = detail.0.values().next().unwrap();
+        assert_eq!(titles["Coding"], minutes(12));
+        assert_eq!(titles["Review"], minutes(18));
+    }
+
+    #[test]
+    fn goal_progress() {
+        use crate::test_tz::Cet;
+        // 2023-03-26 is a Sunday when DST starts
+        let at = |d, h| cet(3, d, h);
+        let entries = vec![
+            entry_between("reading/books", &[], at(19, 20), at(20, 2)),
+            entry_between("reading", &[], at(25, 22), at(26, 4)),
+            entry_between("Sync", &["meeting"], at(26, 9), at(26, 12)),
+        ];
+        let goals = vec![
+            Goal::of_hours("reading", GoalPeriod::Week, GoalKind::Target, 8),
+            Goal::of_hours("reading", GoalPeriod::Day, GoalKind::Target, 2),
+            Goal::of_hours("tag:meeting", GoalPeriod::Week, GoalKind::Cap, 2),
+        ];
+        let today = NaiveDate::from_ymd_opt(2023, 3, 26).unwrap();
+
+        let view = GoalProgressView::new_in(&goals, &entries, today, Weekday::Mon, &Cet);
+        assert_eq!(
+            view.to_string(),
+            "reading: 7:00 of 8:00 target a week, 1:00 remaining\n\
+             reading: 3:00 of 2:00 target a day, reached\n\
+             tag:meeting: 3:00 of 2:00 cap a week, exceeded by 1:00\n"
+        );
+        assert_eq!(
+            view.exceeded_caps("Sync", &["meeting".to_string()]).len(),
+            1
+        );
+        assert!(view.exceeded_caps("reading", &[]).is_empty());
+
+        // the week starting on Sunday has the night of Sunday only
+        let view = GoalProgressView::new_in(&goals, &entries, today, Weekday::Sun, &Cet);
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json[0]["spent_seconds"], 3 * 3600);
+        assert_eq!(json[0]["remaining_seconds"], 5 * 3600);
+        assert_eq!(json[2]["subject"], "tag:meeting");
+        assert_eq!(json[2]["kind"], "cap");
+        assert_eq!(json[2]["remaining_seconds"], -3600);
+    }
+}